@@ -37,4 +37,187 @@ pub enum ErrorCode {
 
     #[msg("Invalid Merkle Proof: computed root does not match.")]
     InvalidMerkleProof,
+
+    #[msg("The program is paused.")]
+    ProgramPaused,
+
+    #[msg("Signer is not authorized for this action.")]
+    Unauthorized,
+
+    #[msg("The campaign is paused.")]
+    CampaignIsPaused,
+
+    #[msg("The campaign is not paused.")]
+    CampaignNotPaused,
+
+    #[msg("Campaign name is too long.")]
+    NameTooLong,
+
+    #[msg("Metadata URI is too long.")]
+    MetadataUriTooLong,
+
+    #[msg("Start time must not be in the past.")]
+    StartInPast,
+
+    #[msg("Campaign duration exceeds the configured maximum.")]
+    DurationTooLong,
+
+    #[msg("Invalid config value.")]
+    InvalidConfig,
+
+    #[msg("Maximum number of deadline extensions reached.")]
+    TooManyExtensions,
+
+    #[msg("New deadline must be in the future and differ from the current one.")]
+    InvalidDeadline,
+
+    #[msg("Not eligible to exit after a deadline change.")]
+    ExitNotAllowed,
+
+    #[msg("Withdrawal is locked during the deadline change grace period.")]
+    GracePeriodActive,
+
+    #[msg("Legacy account is missing or does not match.")]
+    InvalidLegacyAccount,
+
+    #[msg("Pledge can no longer be withdrawn.")]
+    UnpledgeNotAllowed,
+
+    #[msg("Unpledge amount exceeds the pledged amount.")]
+    InsufficientPledge,
+
+    #[msg("Unknown funding model.")]
+    InvalidFundingModel,
+
+    #[msg("Reward tier does not exist.")]
+    InvalidRewardTier,
+
+    #[msg("Pledge is below the reward tier minimum.")]
+    PledgeBelowTierMinimum,
+
+    #[msg("Reward tier is sold out.")]
+    RewardTierSoldOut,
+
+    #[msg("Maximum number of reward tiers reached.")]
+    TooManyRewardTiers,
+
+    #[msg("Backer badge has already been minted.")]
+    BadgeAlreadyMinted,
+
+    #[msg("Backer badge accounts are missing or invalid.")]
+    InvalidBadgeAccounts,
+
+    #[msg("Pledge tokens are enabled; use the pledge token instructions instead.")]
+    PledgeTokensEnabled,
+
+    #[msg("Pledge tokens are not enabled for this campaign.")]
+    PledgeTokensNotEnabled,
+
+    #[msg("Pledge tokens can only be enabled before the first donation.")]
+    PledgeTokensLocked,
+
+    #[msg("Pledge token accounts are missing or invalid.")]
+    InvalidPledgeAccounts,
+
+    #[msg("Donation memo is too long.")]
+    MemoTooLong,
+
+    #[msg("Donation intent has expired.")]
+    IntentExpired,
+
+    #[msg("Donation intent signature is missing or invalid.")]
+    InvalidIntentSignature,
+
+    #[msg("Matching pool is invalid or already settled.")]
+    InvalidMatchingPool,
+
+    #[msg("Funding round is invalid or the campaign is not part of it.")]
+    InvalidRound,
+
+    #[msg("Funding round is not open.")]
+    RoundNotActive,
+
+    #[msg("Funding round has not ended or is already settled.")]
+    RoundNotSettleable,
+
+    #[msg("Maximum number of campaigns in the round reached.")]
+    TooManyRoundCampaigns,
+
+    #[msg("Stretch goal must exceed the target and previous goals.")]
+    InvalidStretchGoal,
+
+    #[msg("Maximum number of stretch goals reached.")]
+    TooManyStretchGoals,
+
+    #[msg("Referrer is invalid or does not match the referrer stats account.")]
+    InvalidReferrer,
+
+    #[msg("Referral reward is not claimable.")]
+    ReferralNotClaimable,
+
+    #[msg("Vesting cliff must not exceed the vesting duration.")]
+    InvalidVestingSchedule,
+
+    #[msg("No vested funds available to withdraw.")]
+    NothingVested,
+
+    #[msg("Beneficiary is invalid or no change is pending.")]
+    InvalidBeneficiary,
+
+    #[msg("Beneficiary change is still timelocked.")]
+    BeneficiaryTimelockActive,
+
+    #[msg("Co-owner set or approval threshold is invalid.")]
+    InvalidCoOwners,
+
+    #[msg("This action requires co-owner approval.")]
+    ApprovalRequired,
+
+    #[msg("Signer is not an owner or co-owner of the campaign.")]
+    NotCoOwner,
+
+    #[msg("Proposal is already approved by this signer.")]
+    AlreadyApproved,
+
+    #[msg("Proposal does not have enough approvals.")]
+    NotEnoughApprovals,
+
+    #[msg("Proposal has already been executed.")]
+    ProposalExecuted,
+
+    #[msg("Campaign cannot be cancelled.")]
+    CancelNotAllowed,
+
+    #[msg("New owner is invalid or no ownership transfer is pending.")]
+    InvalidOwner,
+
+    #[msg("Allowlist settings are invalid.")]
+    InvalidAllowlist,
+
+    #[msg("Donor is not on the campaign allowlist.")]
+    NotAllowlisted,
+
+    #[msg("Wallet is blocked.")]
+    Blocked,
+
+    #[msg("Withdrawals are locked during the dispute window.")]
+    DisputeWindowActive,
+
+    #[msg("Campaign cannot be flagged.")]
+    DisputeNotAllowed,
+
+    #[msg("Donation has already flagged this campaign.")]
+    AlreadyFlagged,
+
+    #[msg("Campaign is disputed.")]
+    CampaignDisputed,
+
+    #[msg("Proposal has expired.")]
+    ProposalExpired,
+
+    #[msg("Round contributions must be funded by the beneficiary's own wallet.")]
+    RoundFunderMismatch,
+
+    #[msg("Referral share is locked once the campaign opens.")]
+    ReferralShareLocked,
 }
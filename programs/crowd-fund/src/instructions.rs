@@ -5,6 +5,27 @@ pub mod refund;
 pub mod finalize;
 pub mod set_merkle_root;
 pub mod reward_claim;
+pub mod config;
+pub mod pause;
+pub mod metadata;
+pub mod deadline;
+pub mod migrate;
+pub mod unpledge;
+pub mod reward_tier;
+pub mod badge;
+pub mod pledge_token;
+pub mod intent;
+pub mod matching;
+pub mod round;
+pub mod stretch_goal;
+pub mod referral;
+pub mod beneficiary;
+pub mod cancel;
+pub mod co_owner;
+pub mod ownership;
+pub mod allowlist;
+pub mod blocklist;
+pub mod dispute;
 
 pub use admin::*;
 pub use donation::*;
@@ -12,4 +33,25 @@ pub use withdrawal::*;
 pub use refund::*;
 pub use finalize::*;
 pub use set_merkle_root::*;
-pub use reward_claim::*;
\ No newline at end of file
+pub use reward_claim::*;
+pub use config::*;
+pub use pause::*;
+pub use metadata::*;
+pub use deadline::*;
+pub use migrate::*;
+pub use unpledge::*;
+pub use reward_tier::*;
+pub use badge::*;
+pub use pledge_token::*;
+pub use intent::*;
+pub use matching::*;
+pub use round::*;
+pub use stretch_goal::*;
+pub use referral::*;
+pub use beneficiary::*;
+pub use cancel::*;
+pub use co_owner::*;
+pub use ownership::*;
+pub use allowlist::*;
+pub use blocklist::*;
+pub use dispute::*;
\ No newline at end of file
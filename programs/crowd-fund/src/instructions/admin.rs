@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
-use crate::{error::ErrorCode, state::{CampaignState, Crowdfund}};
+use crate::{error::ErrorCode, state::{CampaignState, Config, Crowdfund, FundingModel, VestingSchedule, MAX_NAME_LEN}};
 
 
 #[event]
@@ -8,6 +8,7 @@ pub struct CrowdfundInitialized {
     pub campaign: Pubkey,
     pub owner: Pubkey,
     pub target_amount: u64,
+    pub funding_model: u8,
 }
 
 #[derive(Accounts)]
@@ -17,6 +18,15 @@ pub struct InitCrowdfund<'info> {
 
     pub mint: InterfaceAccount<'info, Mint>,
 
+    /// CHECK: only its key is stored, withdrawals are paid to its token account
+    pub beneficiary: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
     #[account(
         init,
         payer = payer,
@@ -30,7 +40,7 @@ pub struct InitCrowdfund<'info> {
         init,
         payer = payer,
         space = 8 + Crowdfund::INIT_SPACE,
-        seeds = [payer.key().as_ref()],
+        seeds = [b"campaign", payer.key().as_ref()],
         bump
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
@@ -45,14 +55,27 @@ pub fn proccess_crowdfund(
     name: String, 
     target_amount: u64,
     start_time: i64,
-    end_time: i64
+    end_time: i64,
+    funding_model: u8,
+    vesting: Option<VestingSchedule>
 ) -> Result<()> {
+    let config = &ctx.accounts.config;
+    require!(!config.paused, ErrorCode::ProgramPaused);
+
     // Validate inputs
-    require!(target_amount > 0, ErrorCode::InvalidTargetAmount);
-    require!(start_time < end_time, ErrorCode::InvalidTimeRange);
+    let now = Clock::get()?.unix_timestamp;
+    validate_campaign_params(&name, target_amount, start_time, end_time, now, config.max_campaign_duration)?;
+    require!(FundingModel::from_u8(funding_model).is_some(), ErrorCode::InvalidFundingModel);
+    if let Some(vesting) = vesting {
+        require!(
+            vesting.cliff >= 0 && vesting.duration > 0 && vesting.cliff <= vesting.duration,
+            ErrorCode::InvalidVestingSchedule
+        );
+    }
 
     let crowdfund_account = &mut ctx.accounts.crowdfund_account;
     crowdfund_account.owner = ctx.accounts.payer.key();
+    crowdfund_account.creator = ctx.accounts.payer.key();
     crowdfund_account.name = name;
     crowdfund_account.escrow_account = ctx.accounts.campaign_token_account.key();
     crowdfund_account.target_amount = target_amount;
@@ -61,6 +84,22 @@ pub fn proccess_crowdfund(
     crowdfund_account.end_time = end_time;
     crowdfund_account.state = CampaignState::Active as u8;
     crowdfund_account.is_withdrawals = false;
+    crowdfund_account.is_paused = false;
+    crowdfund_account.paused_at = 0;
+    crowdfund_account.metadata_uri = String::new();
+    crowdfund_account.category = 0;
+    crowdfund_account.image_hash = [0u8; 32];
+    crowdfund_account.extension_count = 0;
+    crowdfund_account.last_extended_at = 0;
+    crowdfund_account.bump = ctx.bumps.crowdfund_account;
+    crowdfund_account.funding_model = funding_model;
+    crowdfund_account.vesting_cliff = vesting.map_or(0, |vesting| vesting.cliff);
+    crowdfund_account.vesting_duration = vesting.map_or(0, |vesting| vesting.duration);
+    crowdfund_account.succeeded_at = 0;
+    crowdfund_account.withdrawn_amount = 0;
+    crowdfund_account.beneficiary = ctx.accounts.beneficiary.key();
+    crowdfund_account.pending_beneficiary = None;
+    crowdfund_account.beneficiary_effective_at = 0;
 
     msg!("Crowdfund initialized for owner: {} with target amount: {}", ctx.accounts.payer.key(), target_amount);
 
@@ -69,7 +108,85 @@ pub fn proccess_crowdfund(
         campaign: crowdfund_account.key(),
         owner: ctx.accounts.payer.key(),
         target_amount,
+        funding_model,
     });
 
     Ok(())
 }
+
+pub fn validate_campaign_params(
+    name: &str,
+    target_amount: u64,
+    start_time: i64,
+    end_time: i64,
+    now: i64,
+    max_duration: i64
+) -> Result<()> {
+    require!(name.len() <= MAX_NAME_LEN, ErrorCode::NameTooLong);
+    require!(target_amount > 0, ErrorCode::InvalidTargetAmount);
+    require!(start_time < end_time, ErrorCode::InvalidTimeRange);
+    require!(start_time >= now, ErrorCode::StartInPast);
+
+    let duration = end_time.checked_sub(start_time).ok_or(ErrorCode::Overflow)?;
+    require!(duration <= max_duration, ErrorCode::DurationTooLong);
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+    const MAX_DURATION: i64 = 30 * 24 * 60 * 60;
+
+    fn validate(name: &str, start_time: i64, end_time: i64) -> Result<()> {
+        validate_campaign_params(name, 1_000, start_time, end_time, NOW, MAX_DURATION)
+    }
+
+    #[test]
+    fn accepts_valid_params() {
+        assert!(validate("campaign", NOW + 60, NOW + 3600).is_ok());
+    }
+
+    #[test]
+    fn name_length_boundary() {
+        let max_name = "a".repeat(MAX_NAME_LEN);
+        assert!(validate(&max_name, NOW, NOW + 60).is_ok());
+
+        let long_name = "a".repeat(MAX_NAME_LEN + 1);
+        assert_eq!(validate(&long_name, NOW, NOW + 60), Err(ErrorCode::NameTooLong.into()));
+    }
+
+    #[test]
+    fn name_length_counts_bytes() {
+        // 17 个三字节字符 = 51 字节
+        let name = "捐".repeat(17);
+        assert_eq!(validate(&name, NOW, NOW + 60), Err(ErrorCode::NameTooLong.into()));
+    }
+
+    #[test]
+    fn start_time_boundary() {
+        assert!(validate("campaign", NOW, NOW + 60).is_ok());
+        assert_eq!(validate("campaign", NOW - 1, NOW + 60), Err(ErrorCode::StartInPast.into()));
+    }
+
+    #[test]
+    fn duration_boundary() {
+        assert!(validate("campaign", NOW, NOW + MAX_DURATION).is_ok());
+        assert_eq!(
+            validate("campaign", NOW, NOW + MAX_DURATION + 1),
+            Err(ErrorCode::DurationTooLong.into())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_time_range_and_target() {
+        assert_eq!(validate("campaign", NOW + 60, NOW + 60), Err(ErrorCode::InvalidTimeRange.into()));
+        assert_eq!(
+            validate_campaign_params("campaign", 0, NOW, NOW + 60, NOW, MAX_DURATION),
+            Err(ErrorCode::InvalidTargetAmount.into())
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash;
+
+use crate::{
+    error::ErrorCode,
+    instructions::reward_claim::compute_merkle_root,
+    state::{CampaignState, Crowdfund}
+};
+
+
+#[event]
+pub struct AllowlistUpdated {
+    pub campaign: Pubkey,
+    pub allowlist_root: Option<[u8; 32]>,
+    pub allowlist_only: bool,
+    pub early_access_start: i64,
+}
+
+
+#[derive(Accounts)]
+pub struct SetAllowlist<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+// early_access_start 为 0 表示没有提前访问窗口
+pub fn proccess_set_allowlist(
+    ctx: Context<SetAllowlist>,
+    allowlist_root: Option<[u8; 32]>,
+    allowlist_only: bool,
+    early_access_start: i64
+) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(allowlist_root.is_some() || !allowlist_only, ErrorCode::InvalidAllowlist);
+    if early_access_start != 0 {
+        require!(
+            allowlist_root.is_some() && early_access_start > 0 && early_access_start < crowdfund_account.start_time,
+            ErrorCode::InvalidAllowlist
+        );
+    }
+
+    crowdfund_account.allowlist_root = allowlist_root;
+    crowdfund_account.allowlist_only = allowlist_only;
+    crowdfund_account.early_access_start = early_access_start;
+
+    emit!(AllowlistUpdated {
+        campaign: crowdfund_account.key(),
+        allowlist_root,
+        allowlist_only,
+        early_access_start
+    });
+
+    Ok(())
+}
+
+// 校验出资钱包在白名单中，叶子为其公钥的哈希；未提供证明时视为不在白名单
+pub fn verify_allowlist(crowdfund_account: &Crowdfund, funder: &Pubkey, proof: Option<&[[u8; 32]]>) -> Result<bool> {
+    let (Some(allowlist_root), Some(proof)) = (crowdfund_account.allowlist_root, proof) else {
+        return Ok(false);
+    };
+
+    let leaf = hash::hash(funder.as_ref()).to_bytes();
+    require!(compute_merkle_root(leaf, proof) == allowlist_root, ErrorCode::NotAllowlisted);
+
+    Ok(true)
+}
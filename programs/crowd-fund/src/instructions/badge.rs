@@ -0,0 +1,327 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{create_account, CreateAccount};
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_2022::{
+        burn, close_account, initialize_mint2, mint_to, spl_token_2022::{extension::ExtensionType, state::Mint as MintState},
+        Burn, CloseAccount, InitializeMint2, MintTo, Token2022
+    },
+    token_interface::{
+        Mint, TokenAccount, metadata_pointer_initialize, mint_close_authority_initialize, non_transferable_mint_initialize,
+        permanent_delegate_initialize,
+        spl_pod::optional_keys::OptionalNonZeroPubkey,
+        spl_token_metadata_interface::state::{Field, TokenMetadata},
+        token_metadata_initialize, token_metadata_update_field, MetadataPointerInitialize, MintCloseAuthorityInitialize,
+        NonTransferableMintInitialize, PermanentDelegateInitialize, TokenMetadataInitialize,
+        TokenMetadataUpdateField
+    }
+};
+
+use crate::{error::ErrorCode, state::{Crowdfund, DonationRecord}};
+
+pub const BADGE_SYMBOL: &str = "BACKER";
+
+
+#[event]
+pub struct BackerBadgeMinted {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub tier: Option<u8>,
+}
+
+
+#[derive(Accounts)]
+pub struct MintBackerBadge<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+
+    /// CHECK: created in the handler as a non-transferable Token-2022 mint
+    #[account(
+        mut,
+        seeds = [b"badge", donation_record_account.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: UncheckedAccount<'info>,
+
+    /// CHECK: donor's associated token account for the badge, created by the associated token program,
+    /// an empty account left by an earlier burned badge of the same record is reused
+    #[account(mut)]
+    pub badge_token_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_2022_program: Program<'info, Token2022>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+pub fn proccess_mint_backer_badge(ctx: Context<MintBackerBadge>) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let donation_record_account = &ctx.accounts.donation_record_account;
+
+    require!(!donation_record_account.is_refunded, ErrorCode::AlreadyRefunded);
+    require!(!donation_record_account.has_badge, ErrorCode::BadgeAlreadyMinted);
+
+    let crowdfund_key = crowdfund_account.key();
+    let mint_key = ctx.accounts.badge_mint.key();
+    let token_program = ctx.accounts.token_2022_program.to_account_info();
+    let badge_mint = ctx.accounts.badge_mint.to_account_info();
+    let crowdfund_info = crowdfund_account.to_account_info();
+
+    let amount = donation_record_account.amount.to_string();
+    let tier = donation_record_account.tier.map_or(String::new(), |index| index.to_string());
+
+    // 预先按完整元数据大小充值租金，元数据扩展由 Token-2022 自行扩容
+    let metadata = TokenMetadata {
+        update_authority: OptionalNonZeroPubkey(crowdfund_key),
+        mint: mint_key,
+        name: crowdfund_account.name.clone(),
+        symbol: BADGE_SYMBOL.to_string(),
+        uri: crowdfund_account.metadata_uri.clone(),
+        additional_metadata: vec![
+            ("amount".to_string(), amount.clone()),
+            ("tier".to_string(), tier.clone()),
+        ],
+    };
+    let space = ExtensionType::try_calculate_account_len::<MintState>(&[
+        ExtensionType::NonTransferable,
+        ExtensionType::PermanentDelegate,
+        ExtensionType::MintCloseAuthority,
+        ExtensionType::MetadataPointer,
+    ])?;
+    let lamports = Rent::get()?.minimum_balance(space + metadata.tlv_size_of()?);
+
+    let record_key = donation_record_account.key();
+    let mint_seeds: &[&[&[u8]]] = &[&[
+        b"badge",
+        record_key.as_ref(),
+        &[ctx.bumps.badge_mint]
+    ]];
+
+    create_account(
+        CpiContext::new_with_signer(
+            ctx.accounts.system_program.to_account_info(),
+            CreateAccount {
+                from: ctx.accounts.donor.to_account_info(),
+                to: badge_mint.clone(),
+            },
+            mint_seeds
+        ),
+        lamports,
+        space as u64,
+        token_program.key
+    )?;
+
+    // 扩展必须在 initialize_mint2 之前初始化
+    non_transferable_mint_initialize(CpiContext::new(
+        token_program.clone(),
+        NonTransferableMintInitialize {
+            token_program_id: token_program.clone(),
+            mint: badge_mint.clone(),
+        }
+    ))?;
+
+    // 众筹 PDA 作为永久代理，退款时无需捐赠者签名即可销毁徽章
+    permanent_delegate_initialize(
+        CpiContext::new(
+            token_program.clone(),
+            PermanentDelegateInitialize {
+                token_program_id: token_program.clone(),
+                mint: badge_mint.clone(),
+            }
+        ),
+        &crowdfund_key
+    )?;
+
+    // 徽章销毁时关闭铸币账户，捐赠记录重建后可以在同一地址重新铸造
+    mint_close_authority_initialize(
+        CpiContext::new(
+            token_program.clone(),
+            MintCloseAuthorityInitialize {
+                token_program_id: token_program.clone(),
+                mint: badge_mint.clone(),
+            }
+        ),
+        Some(&crowdfund_key)
+    )?;
+
+    metadata_pointer_initialize(
+        CpiContext::new(
+            token_program.clone(),
+            MetadataPointerInitialize {
+                token_program_id: token_program.clone(),
+                mint: badge_mint.clone(),
+            }
+        ),
+        Some(crowdfund_key),
+        Some(mint_key)
+    )?;
+
+    initialize_mint2(
+        CpiContext::new(
+            token_program.clone(),
+            InitializeMint2 {
+                mint: badge_mint.clone(),
+            }
+        ),
+        0,
+        &crowdfund_key,
+        None
+    )?;
+
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    token_metadata_initialize(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            TokenMetadataInitialize {
+                program_id: token_program.clone(),
+                metadata: badge_mint.clone(),
+                update_authority: crowdfund_info.clone(),
+                mint_authority: crowdfund_info.clone(),
+                mint: badge_mint.clone(),
+            },
+            signer_seeds
+        ),
+        metadata.name,
+        metadata.symbol,
+        metadata.uri
+    )?;
+
+    for (key, value) in [("amount", amount), ("tier", tier)] {
+        token_metadata_update_field(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataUpdateField {
+                    program_id: token_program.clone(),
+                    metadata: badge_mint.clone(),
+                    update_authority: crowdfund_info.clone(),
+                },
+                signer_seeds
+            ),
+            Field::Key(key.to_string()),
+            value
+        )?;
+    }
+
+    create_idempotent(CpiContext::new(
+        ctx.accounts.associated_token_program.to_account_info(),
+        Create {
+            payer: ctx.accounts.donor.to_account_info(),
+            associated_token: ctx.accounts.badge_token_account.to_account_info(),
+            authority: ctx.accounts.donor.to_account_info(),
+            mint: badge_mint.clone(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            token_program: token_program.clone(),
+        }
+    ))?;
+
+    mint_to(
+        CpiContext::new_with_signer(
+            token_program.clone(),
+            MintTo {
+                mint: badge_mint,
+                to: ctx.accounts.badge_token_account.to_account_info(),
+                authority: crowdfund_info,
+            },
+            signer_seeds
+        ),
+        1
+    )?;
+
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    donation_record_account.has_badge = true;
+
+    emit!(BackerBadgeMinted {
+        campaign: crowdfund_key,
+        donor: ctx.accounts.donor.key(),
+        mint: mint_key,
+        amount: donation_record_account.amount,
+        tier: donation_record_account.tier
+    });
+
+    Ok(())
+}
+
+// 资金退回支持者时销毁其徽章（众筹 PDA 为永久代理），并关闭铸币账户把租金退给支持者
+pub fn burn_backer_badge<'info>(
+    crowdfund_account: &Account<'info, Crowdfund>,
+    donation_record_account: &mut DonationRecord,
+    badge_mint: Option<&InterfaceAccount<'info, Mint>>,
+    badge_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    token_2022_program: Option<&Program<'info, Token2022>>,
+    donor: AccountInfo<'info>
+) -> Result<()> {
+    if !donation_record_account.has_badge {
+        return Ok(());
+    }
+
+    let (Some(badge_mint), Some(badge_token_account), Some(token_2022_program)) = (
+        badge_mint,
+        badge_token_account,
+        token_2022_program
+    ) else {
+        return Err(ErrorCode::InvalidBadgeAccounts.into());
+    };
+    require!(
+        badge_token_account.mint == badge_mint.key()
+            && badge_token_account.owner == donation_record_account.donor
+            && donor.key() == donation_record_account.donor,
+        ErrorCode::InvalidBadgeAccounts
+    );
+
+    let creator_key = crowdfund_account.creator;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        creator_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_2022_program.to_account_info(),
+        Burn {
+            mint: badge_mint.to_account_info(),
+            from: badge_token_account.to_account_info(),
+            authority: crowdfund_account.to_account_info(),
+        },
+        signer_seeds
+    );
+
+    burn(cpi_ctx, badge_token_account.amount)?;
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_2022_program.to_account_info(),
+        CloseAccount {
+            account: badge_mint.to_account_info(),
+            destination: donor,
+            authority: crowdfund_account.to_account_info(),
+        },
+        signer_seeds
+    );
+
+    close_account(cpi_ctx)?;
+    donation_record_account.has_badge = false;
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{Config, Crowdfund}};
+
+
+#[event]
+pub struct BeneficiaryChangeProposed {
+    pub campaign: Pubkey,
+    pub new_beneficiary: Pubkey,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct BeneficiaryChanged {
+    pub campaign: Pubkey,
+    pub previous_beneficiary: Pubkey,
+    pub new_beneficiary: Pubkey,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdateBeneficiary<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+pub fn proccess_propose_beneficiary(ctx: Context<UpdateBeneficiary>, new_beneficiary: Pubkey) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    // 设置了共同所有者时需走提案审批，否则所有者可以把已批准的提款转到自己指定的钱包
+    require!(!crowdfund_account.requires_approval(), ErrorCode::ApprovalRequired);
+
+    propose_beneficiary(crowdfund_account, &ctx.accounts.config, new_beneficiary)
+}
+
+// 新的收款人需等待时间锁结束才能生效，支持者可以提前看到变更
+pub fn propose_beneficiary(
+    crowdfund_account: &mut Account<Crowdfund>,
+    config: &Config,
+    new_beneficiary: Pubkey
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(new_beneficiary != crowdfund_account.beneficiary, ErrorCode::InvalidBeneficiary);
+
+    let effective_at = now
+        .checked_add(config.beneficiary_timelock)
+        .ok_or(ErrorCode::Overflow)?;
+
+    crowdfund_account.pending_beneficiary = Some(new_beneficiary);
+    crowdfund_account.beneficiary_effective_at = effective_at;
+
+    emit!(BeneficiaryChangeProposed {
+        campaign: crowdfund_account.key(),
+        new_beneficiary,
+        effective_at
+    });
+
+    Ok(())
+}
+
+pub fn proccess_apply_beneficiary_change(ctx: Context<UpdateBeneficiary>) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    let new_beneficiary = crowdfund_account.pending_beneficiary.ok_or(ErrorCode::InvalidBeneficiary)?;
+    require!(now >= crowdfund_account.beneficiary_effective_at, ErrorCode::BeneficiaryTimelockActive);
+
+    let previous_beneficiary = crowdfund_account.beneficiary;
+    crowdfund_account.beneficiary = new_beneficiary;
+    crowdfund_account.pending_beneficiary = None;
+    crowdfund_account.beneficiary_effective_at = 0;
+
+    emit!(BeneficiaryChanged {
+        campaign: crowdfund_account.key(),
+        previous_beneficiary,
+        new_beneficiary
+    });
+
+    Ok(())
+}
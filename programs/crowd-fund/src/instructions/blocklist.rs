@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{BlockedWallet, Config}};
+
+
+#[event]
+pub struct WalletBlocked {
+    pub wallet: Pubkey,
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct WalletUnblocked {
+    pub wallet: Pubkey,
+    pub admin: Pubkey,
+}
+
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BlockWallet<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + BlockedWallet::INIT_SPACE,
+        seeds = [b"blocked", wallet.as_ref()],
+        bump
+    )]
+    pub blocked_wallet: Account<'info, BlockedWallet>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct UnblockWallet<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"blocked", blocked_wallet.wallet.as_ref()],
+        bump = blocked_wallet.bump
+    )]
+    pub blocked_wallet: Account<'info, BlockedWallet>,
+}
+
+
+pub fn proccess_block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+    let blocked_wallet = &mut ctx.accounts.blocked_wallet;
+    blocked_wallet.wallet = wallet;
+    blocked_wallet.blocked_at = Clock::get()?.unix_timestamp;
+    blocked_wallet.bump = ctx.bumps.blocked_wallet;
+
+    emit!(WalletBlocked {
+        wallet,
+        admin: ctx.accounts.admin.key()
+    });
+
+    Ok(())
+}
+
+pub fn proccess_unblock_wallet(ctx: Context<UnblockWallet>) -> Result<()> {
+    emit!(WalletUnblocked {
+        wallet: ctx.accounts.blocked_wallet.wallet,
+        admin: ctx.accounts.admin.key()
+    });
+
+    Ok(())
+}
+
+// 黑名单条目是以钱包为种子的 PDA，未被封禁时该地址没有数据
+pub fn is_blocked(blocklist_entry: &AccountInfo) -> bool {
+    blocklist_entry.owner == &crate::ID && !blocklist_entry.data_is_empty()
+}
+
+pub fn require_not_blocked(blocklist_entry: &AccountInfo) -> Result<()> {
+    require!(!is_blocked(blocklist_entry), ErrorCode::Blocked);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{CampaignState, Config, Crowdfund}};
+
+
+#[event]
+pub struct CampaignCancelled {
+    pub campaign: Pubkey,
+    pub raised_amount: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct CancelCampaign<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+pub fn proccess_cancel_campaign(ctx: Context<CancelCampaign>) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    // 设置了共同所有者时需走提案审批
+    require!(!crowdfund_account.requires_approval(), ErrorCode::ApprovalRequired);
+
+    cancel_campaign(crowdfund_account, &ctx.accounts.config)
+}
+
+// 取消后众筹视为失败，所有支持者都可以退款；成功结果确定后不能再取消
+pub fn cancel_campaign(crowdfund_account: &mut Account<Crowdfund>, config: &Config) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        !crowdfund_account.is_withdrawals && !crowdfund_account.is_final(config, now),
+        ErrorCode::CancelNotAllowed
+    );
+
+    crowdfund_account.state = CampaignState::Fail as u8;
+    crowdfund_account.is_cancelled = true;
+
+    emit!(CampaignCancelled {
+        campaign: crowdfund_account.key(),
+        raised_amount: crowdfund_account.raised_amount
+    });
+
+    Ok(())
+}
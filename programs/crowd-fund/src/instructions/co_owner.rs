@@ -0,0 +1,350 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    error::ErrorCode,
+    instructions::{
+        beneficiary::propose_beneficiary,
+        cancel::cancel_campaign,
+        metadata::{apply_metadata, validate_metadata},
+        set_merkle_root::apply_merkle_root,
+        withdrawal::{withdraw_vested, WithdrawMade}
+    },
+    state::{CampaignAction, Config, Crowdfund, Proposal, MAX_CO_OWNERS}
+};
+
+
+#[event]
+pub struct CoOwnersSet {
+    pub campaign: Pubkey,
+    pub co_owners: Vec<Pubkey>,
+    pub approval_threshold: u8,
+}
+
+#[event]
+pub struct ActionProposed {
+    pub campaign: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: CampaignAction,
+}
+
+#[event]
+pub struct ActionApproved {
+    pub campaign: Pubkey,
+    pub proposal_id: u64,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct ActionExecuted {
+    pub campaign: Pubkey,
+    pub proposal_id: u64,
+    pub executor: Pubkey,
+}
+
+
+#[derive(Accounts)]
+pub struct SetCoOwners<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAction<'info> {
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + Proposal::INIT_SPACE,
+        seeds = [
+            b"proposal",
+            crowdfund_account.key().as_ref(),
+            crowdfund_account.proposal_count.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct ApproveAction<'info> {
+    pub approver: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", crowdfund_account.key().as_ref(), proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteAction<'info> {
+    #[account(mut)]
+    pub executor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    // 修改名称时可能需要扩容
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump,
+        realloc = proposal.campaign_space(&crowdfund_account),
+        realloc::payer = executor,
+        realloc::zero = false
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"proposal", crowdfund_account.key().as_ref(), proposal.proposal_id.to_le_bytes().as_ref()],
+        bump = proposal.bump
+    )]
+    pub proposal: Account<'info, Proposal>,
+
+    /// CHECK: blocklist entry for the campaign beneficiary, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", crowdfund_account.beneficiary.as_ref()],
+        bump
+    )]
+    pub beneficiary_blocklist: UncheckedAccount<'info>,
+
+    // 以下账户仅在执行提款时需要
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub campaign_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub withdraw_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
+    pub system_program: Program<'info, System>
+}
+
+
+// 所有者只能直接设置一次，之后敏感操作都需要达到门槛数量的批准，
+// 更换或移除共同所有者需通过 CampaignAction::SetCoOwners 提案
+pub fn proccess_set_co_owners(ctx: Context<SetCoOwners>, co_owners: Vec<Pubkey>, approval_threshold: u8) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(!crowdfund_account.requires_approval(), ErrorCode::InvalidCoOwners);
+    require!(!co_owners.is_empty(), ErrorCode::InvalidCoOwners);
+
+    // 之前由所有者单独发起的收款人变更未经共同所有者批准，作废
+    crowdfund_account.pending_beneficiary = None;
+    crowdfund_account.beneficiary_effective_at = 0;
+
+    apply_co_owners(crowdfund_account, co_owners, approval_threshold)
+}
+
+// 空列表且门槛为零表示恢复为所有者单独管理
+pub fn apply_co_owners(
+    crowdfund_account: &mut Account<Crowdfund>,
+    co_owners: Vec<Pubkey>,
+    approval_threshold: u8
+) -> Result<()> {
+    require!(co_owners.len() <= MAX_CO_OWNERS, ErrorCode::InvalidCoOwners);
+    for (index, co_owner) in co_owners.iter().enumerate() {
+        require!(
+            *co_owner != crowdfund_account.owner && !co_owners[..index].contains(co_owner),
+            ErrorCode::InvalidCoOwners
+        );
+    }
+    // 所有者本身也算一票
+    if co_owners.is_empty() {
+        require!(approval_threshold == 0, ErrorCode::InvalidCoOwners);
+    } else {
+        require!(
+            approval_threshold > 0 && approval_threshold as usize <= co_owners.len() + 1,
+            ErrorCode::InvalidCoOwners
+        );
+    }
+
+    crowdfund_account.co_owners = co_owners.clone();
+    crowdfund_account.approval_threshold = approval_threshold;
+
+    emit!(CoOwnersSet {
+        campaign: crowdfund_account.key(),
+        co_owners,
+        approval_threshold
+    });
+
+    Ok(())
+}
+
+pub fn proccess_propose_action(ctx: Context<ProposeAction>, action: CampaignAction) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let proposer = ctx.accounts.proposer.key();
+
+    require!(crowdfund_account.requires_approval(), ErrorCode::InvalidCoOwners);
+    require!(crowdfund_account.is_member(&proposer), ErrorCode::NotCoOwner);
+    match &action {
+        CampaignAction::UpdateMetadata { name, metadata_uri, .. } => validate_metadata(name, metadata_uri)?,
+        CampaignAction::SetCoOwners { co_owners, .. } => {
+            require!(co_owners.len() <= MAX_CO_OWNERS, ErrorCode::InvalidCoOwners);
+        },
+        _ => {},
+    }
+
+    let proposal_id = crowdfund_account.proposal_count;
+    crowdfund_account.proposal_count = proposal_id.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+    // 提案人自动计为一票批准
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.campaign = crowdfund_account.key();
+    proposal.proposal_id = proposal_id;
+    proposal.proposer = proposer;
+    proposal.action = action.clone();
+    proposal.approvals = vec![proposer];
+    proposal.is_executed = false;
+    proposal.created_at = Clock::get()?.unix_timestamp;
+    proposal.bump = ctx.bumps.proposal;
+
+    emit!(ActionProposed {
+        campaign: crowdfund_account.key(),
+        proposal_id,
+        proposer,
+        action
+    });
+
+    Ok(())
+}
+
+pub fn proccess_approve_action(ctx: Context<ApproveAction>) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let proposal = &mut ctx.accounts.proposal;
+    let approver = ctx.accounts.approver.key();
+
+    require!(crowdfund_account.is_member(&approver), ErrorCode::NotCoOwner);
+    require!(!proposal.is_executed, ErrorCode::ProposalExecuted);
+    require!(!proposal.is_expired(Clock::get()?.unix_timestamp), ErrorCode::ProposalExpired);
+    require!(!proposal.approvals.contains(&approver), ErrorCode::AlreadyApproved);
+
+    proposal.approvals.push(approver);
+
+    emit!(ActionApproved {
+        campaign: crowdfund_account.key(),
+        proposal_id: proposal.proposal_id,
+        approver,
+        approvals: proposal.approvals.len() as u8
+    });
+
+    Ok(())
+}
+
+pub fn proccess_execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+    let executor = ctx.accounts.executor.key();
+    let proposal = &ctx.accounts.proposal;
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.is_member(&executor), ErrorCode::NotCoOwner);
+    require!(!proposal.is_executed, ErrorCode::ProposalExecuted);
+    // 过期的提案不再执行，情况变化后需重新提案
+    require!(!proposal.is_expired(Clock::get()?.unix_timestamp), ErrorCode::ProposalExpired);
+    // 所有权转移后，前任所有者的批准不再计入
+    let approvals = proposal.approvals
+        .iter()
+        .filter(|approver| crowdfund_account.is_member(approver))
+        .count();
+    require!(approvals >= crowdfund_account.approval_threshold as usize, ErrorCode::NotEnoughApprovals);
+
+    match proposal.action.clone() {
+        CampaignAction::Withdraw => {
+            let (
+                Some(mint),
+                Some(campaign_token_account),
+                Some(withdraw_token_account),
+                Some(token_program)
+            ) = (
+                &ctx.accounts.mint,
+                &ctx.accounts.campaign_token_account,
+                &ctx.accounts.withdraw_token_account,
+                &ctx.accounts.token_program
+            ) else {
+                return Err(ErrorCode::WithdrawalNotAllowed.into());
+            };
+
+            let amount = withdraw_vested(
+                &ctx.accounts.config,
+                crowdfund_account,
+                campaign_token_account,
+                withdraw_token_account,
+                &ctx.accounts.beneficiary_blocklist,
+                mint,
+                token_program
+            )?;
+
+            emit!(WithdrawMade {
+                withdrawer: executor,
+                beneficiary: crowdfund_account.beneficiary,
+                amount,
+                withdrawn_amount: crowdfund_account.withdrawn_amount
+            });
+        },
+        CampaignAction::SetMerkleRoot { merkle_root } => {
+            apply_merkle_root(crowdfund_account, merkle_root)?;
+        },
+        CampaignAction::Cancel => {
+            cancel_campaign(crowdfund_account, &ctx.accounts.config)?;
+        },
+        CampaignAction::UpdateMetadata { name, metadata_uri, category, image_hash } => {
+            apply_metadata(crowdfund_account, name, metadata_uri, category, image_hash)?;
+        },
+        CampaignAction::ProposeBeneficiary { new_beneficiary } => {
+            propose_beneficiary(crowdfund_account, &ctx.accounts.config, new_beneficiary)?;
+        },
+        CampaignAction::SetCoOwners { co_owners, approval_threshold } => {
+            apply_co_owners(crowdfund_account, co_owners, approval_threshold)?;
+        },
+    }
+
+    let proposal = &mut ctx.accounts.proposal;
+    proposal.is_executed = true;
+
+    emit!(ActionExecuted {
+        campaign: proposal.campaign,
+        proposal_id: proposal.proposal_id,
+        executor
+    });
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, program::CrowdFund, state::{
+    Config, BPS_DENOMINATOR, DEFAULT_BENEFICIARY_TIMELOCK, DEFAULT_DISPUTE_THRESHOLD_BPS, DEFAULT_DISPUTE_WINDOW,
+    DEFAULT_EXTENSION_GRACE_PERIOD, DEFAULT_MAX_CAMPAIGN_DURATION, DEFAULT_MAX_DEADLINE_EXTENSIONS,
+    DEFAULT_MAX_PAUSE_DURATION
+}};
+
+
+#[event]
+pub struct ConfigInitialized {
+    pub admin: Pubkey,
+}
+
+#[event]
+pub struct PauseChanged {
+    pub admin: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct CampaignLimitsUpdated {
+    pub max_campaign_duration: i64,
+    pub max_pause_duration: i64,
+}
+
+#[event]
+pub struct ExtensionPolicyUpdated {
+    pub max_deadline_extensions: u8,
+    pub extension_grace_period: i64,
+}
+
+#[event]
+pub struct PledgePolicyUpdated {
+    pub lock_pledges_on_success: bool,
+}
+
+#[event]
+pub struct BeneficiaryTimelockUpdated {
+    pub beneficiary_timelock: i64,
+}
+
+#[event]
+pub struct DisputePolicyUpdated {
+    pub arbiter: Pubkey,
+    pub dispute_window: i64,
+    pub dispute_threshold_bps: u16,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    // Only the upgrade authority of this program may create the config
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, CrowdFund>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    pub pending_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+
+pub fn proccess_init_config(ctx: Context<InitConfig>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.admin = ctx.accounts.authority.key();
+    config.pending_admin = None;
+    config.paused = false;
+    config.bump = ctx.bumps.config;
+    config.max_campaign_duration = DEFAULT_MAX_CAMPAIGN_DURATION;
+    config.max_deadline_extensions = DEFAULT_MAX_DEADLINE_EXTENSIONS;
+    config.extension_grace_period = DEFAULT_EXTENSION_GRACE_PERIOD;
+    config.lock_pledges_on_success = false;
+    config.beneficiary_timelock = DEFAULT_BENEFICIARY_TIMELOCK;
+    config.arbiter = ctx.accounts.authority.key();
+    config.dispute_window = DEFAULT_DISPUTE_WINDOW;
+    config.dispute_threshold_bps = DEFAULT_DISPUTE_THRESHOLD_BPS;
+    config.max_pause_duration = DEFAULT_MAX_PAUSE_DURATION;
+
+    emit!(ConfigInitialized {
+        admin: config.admin
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    config.paused = paused;
+
+    msg!("Program paused: {}", paused);
+
+    emit!(PauseChanged {
+        admin: config.admin,
+        paused
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_campaign_limits(
+    ctx: Context<UpdateConfig>,
+    max_campaign_duration: i64,
+    max_pause_duration: i64
+) -> Result<()> {
+    require!(max_campaign_duration > 0, ErrorCode::InvalidConfig);
+    require!(max_pause_duration >= 0, ErrorCode::InvalidConfig);
+
+    let config = &mut ctx.accounts.config;
+    config.max_campaign_duration = max_campaign_duration;
+    config.max_pause_duration = max_pause_duration;
+
+    emit!(CampaignLimitsUpdated {
+        max_campaign_duration,
+        max_pause_duration
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_extension_policy(
+    ctx: Context<UpdateConfig>,
+    max_deadline_extensions: u8,
+    extension_grace_period: i64
+) -> Result<()> {
+    require!(extension_grace_period >= 0, ErrorCode::InvalidConfig);
+
+    let config = &mut ctx.accounts.config;
+    config.max_deadline_extensions = max_deadline_extensions;
+    config.extension_grace_period = extension_grace_period;
+
+    emit!(ExtensionPolicyUpdated {
+        max_deadline_extensions,
+        extension_grace_period
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_pledge_policy(ctx: Context<UpdateConfig>, lock_pledges_on_success: bool) -> Result<()> {
+    ctx.accounts.config.lock_pledges_on_success = lock_pledges_on_success;
+
+    emit!(PledgePolicyUpdated {
+        lock_pledges_on_success
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_beneficiary_timelock(ctx: Context<UpdateConfig>, beneficiary_timelock: i64) -> Result<()> {
+    require!(beneficiary_timelock >= 0, ErrorCode::InvalidConfig);
+
+    ctx.accounts.config.beneficiary_timelock = beneficiary_timelock;
+
+    emit!(BeneficiaryTimelockUpdated {
+        beneficiary_timelock
+    });
+
+    Ok(())
+}
+
+pub fn proccess_set_dispute_policy(
+    ctx: Context<UpdateConfig>,
+    arbiter: Pubkey,
+    dispute_window: i64,
+    dispute_threshold_bps: u16
+) -> Result<()> {
+    require!(dispute_window >= 0, ErrorCode::InvalidConfig);
+    require!(
+        dispute_threshold_bps > 0 && dispute_threshold_bps as u64 <= BPS_DENOMINATOR,
+        ErrorCode::InvalidConfig
+    );
+
+    let config = &mut ctx.accounts.config;
+    config.arbiter = arbiter;
+    config.dispute_window = dispute_window;
+    config.dispute_threshold_bps = dispute_threshold_bps;
+
+    emit!(DisputePolicyUpdated {
+        arbiter,
+        dispute_window,
+        dispute_threshold_bps
+    });
+
+    Ok(())
+}
+
+pub fn proccess_propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+    // 两步转移：先记录待定管理员，由新管理员签名接受
+    ctx.accounts.config.pending_admin = Some(new_admin);
+
+    Ok(())
+}
+
+pub fn proccess_accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    let pending_admin = ctx.accounts.pending_admin.key();
+
+    require!(config.pending_admin == Some(pending_admin), ErrorCode::Unauthorized);
+
+    let previous_admin = config.admin;
+    config.admin = pending_admin;
+    config.pending_admin = None;
+
+    emit!(AdminTransferred {
+        previous_admin,
+        new_admin: pending_admin
+    });
+
+    Ok(())
+}
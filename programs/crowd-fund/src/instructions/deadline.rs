@@ -0,0 +1,285 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::{
+        badge::burn_backer_badge,
+        blocklist::require_not_blocked,
+        pledge_token::burn_pledge_tokens,
+        referral::release_referral,
+        round::sync_round_record
+    },
+    state::{CampaignState, Config, Crowdfund, DonationRecord, ReferrerStats, Round}
+};
+
+
+#[event]
+pub struct DeadlineExtended {
+    pub campaign: Pubkey,
+    pub previous_end_time: i64,
+    pub new_end_time: i64,
+    pub extension_count: u8,
+    pub grace_period_end: i64,
+}
+
+#[event]
+pub struct ExitAfterExtensionMade {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct ExtendDeadline<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+#[derive(Accounts)]
+pub struct ExitAfterExtension<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = donor,
+        associated_token::token_program = token_program
+    )]
+    pub donation_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // 捐款计入了资助轮次时必须传入
+    #[account(mut)]
+    pub round: Option<Account<'info, Round>>,
+
+    // 捐款带有推荐人时必须传入
+    #[account(mut)]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    // 众筹启用了质押凭证时需要，按撤回金额销毁
+    #[account(
+        mut,
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub pledge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub pledge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // 仅当捐赠记录铸造过支持者徽章、且本次退回全部捐款时需要
+    #[account(
+        mut,
+        seeds = [b"badge", donation_record_account.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub badge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: blocklist entry for the donor, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", donor.key().as_ref()],
+        bump
+    )]
+    pub donor_blocklist: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_2022_program: Option<Program<'info, Token2022>>
+}
+
+
+pub fn proccess_extend_deadline(ctx: Context<ExtendDeadline>, new_end_time: i64) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(now <= crowdfund_account.end_time, ErrorCode::CampaignExpired);
+    require!(
+        crowdfund_account.extension_count < config.max_deadline_extensions,
+        ErrorCode::TooManyExtensions
+    );
+    require!(
+        new_end_time > now && new_end_time != crowdfund_account.end_time,
+        ErrorCode::InvalidDeadline
+    );
+
+    let duration = new_end_time
+        .checked_sub(crowdfund_account.start_time)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(duration <= config.max_campaign_duration, ErrorCode::DurationTooLong);
+
+    let previous_end_time = crowdfund_account.end_time;
+    crowdfund_account.end_time = new_end_time;
+    crowdfund_account.extension_count += 1;
+    crowdfund_account.last_extended_at = now;
+
+    let grace_period_end = crowdfund_account
+        .grace_period_end(config.extension_grace_period)
+        .ok_or(ErrorCode::Overflow)?;
+
+    msg!("Deadline changed from {} to {}", previous_end_time, new_end_time);
+
+    emit!(DeadlineExtended {
+        campaign: crowdfund_account.key(),
+        previous_end_time,
+        new_end_time,
+        extension_count: crowdfund_account.extension_count,
+        grace_period_end
+    });
+
+    Ok(())
+}
+
+pub fn proccess_exit_after_extension(ctx: Context<ExitAfterExtension>) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let donation_record_account = &ctx.accounts.donation_record_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!donation_record_account.is_refunded, ErrorCode::AlreadyRefunded);
+    require_not_blocked(&ctx.accounts.donor_blocklist)?;
+    require!(
+        donation_record_account.campaign == crowdfund_account.escrow_account,
+        ErrorCode::ExitNotAllowed
+    );
+    // 争议期间资金冻结等待仲裁，退出可能让众筹在争议中跌破目标，因此不允许
+    require!(
+        matches!(crowdfund_account.get_state(), Some(CampaignState::Active) | Some(CampaignState::Success))
+            && !crowdfund_account.is_withdrawals,
+        ErrorCode::ExitNotAllowed
+    );
+
+    // 只有在截止时间变更之前捐款、且仍处于宽限期内的支持者可以退出
+    let grace_period_end = crowdfund_account
+        .grace_period_end(ctx.accounts.config.extension_grace_period)
+        .ok_or(ErrorCode::ExitNotAllowed)?;
+    require!(
+        donation_record_account.donated_at < crowdfund_account.last_extended_at && now <= grace_period_end,
+        ErrorCode::ExitNotAllowed
+    );
+
+    let amount = donation_record_account.amount;
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[ctx.accounts.crowdfund_account.bump]
+    ]];
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.campaign_token_account.to_account_info(),
+        to: ctx.accounts.donation_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    burn_pledge_tokens(
+        &ctx.accounts.crowdfund_account,
+        ctx.accounts.pledge_mint.as_ref(),
+        ctx.accounts.pledge_token_account.as_ref(),
+        &ctx.accounts.donor,
+        &ctx.accounts.token_program,
+        amount
+    )?;
+
+    // 退出后未达到目标时恢复为进行中
+    let matched = ctx.accounts.donation_record_account.matched_amount;
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    crowdfund_account.release_flag(&mut ctx.accounts.donation_record_account, amount);
+    crowdfund_account.release_pledge(amount, matched).ok_or(ErrorCode::Overflow)?;
+    release_referral(
+        crowdfund_account,
+        ctx.accounts.referrer_stats.as_mut(),
+        &ctx.accounts.donation_record_account,
+        amount
+    )?;
+
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    donation_record_account.is_refunded = true;
+    donation_record_account.matched_amount = 0;
+    if let Some(index) = donation_record_account.tier.take() {
+        crowdfund_account.release_tier(index);
+    }
+
+    burn_backer_badge(
+        crowdfund_account,
+        donation_record_account,
+        ctx.accounts.badge_mint.as_ref(),
+        ctx.accounts.badge_token_account.as_ref(),
+        ctx.accounts.token_2022_program.as_ref(),
+        ctx.accounts.donor.to_account_info()
+    )?;
+
+    sync_round_record(ctx.accounts.round.as_mut(), &crowdfund_account.key(), donation_record_account)?;
+
+    emit!(ExitAfterExtensionMade {
+        campaign: crowdfund_account.key(),
+        donor: ctx.accounts.donor.key(),
+        amount
+    });
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{CampaignState, Config, Crowdfund, DonationRecord, BPS_DENOMINATOR}
+};
+
+
+#[event]
+pub struct CampaignFlagged {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub flagged_amount: u64,
+}
+
+#[event]
+pub struct CampaignDisputed {
+    pub campaign: Pubkey,
+    pub flagged_amount: u64,
+    pub raised_amount: u64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub campaign: Pubkey,
+    pub arbiter: Pubkey,
+    pub released: bool,
+}
+
+
+#[derive(Accounts)]
+pub struct FlagCampaign<'info> {
+    pub donor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    pub arbiter: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = arbiter @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+// 争议期内支持者可以标记众筹，标记金额超过阈值后进入争议状态
+pub fn proccess_flag_campaign(ctx: Context<FlagCampaign>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(
+        crowdfund_account.get_state() == Some(CampaignState::Success)
+            && !crowdfund_account.is_withdrawals
+            && !crowdfund_account.is_dispute_resolved,
+        ErrorCode::DisputeNotAllowed
+    );
+    let dispute_window_end = crowdfund_account
+        .dispute_window_end(config.dispute_window)
+        .ok_or(ErrorCode::Overflow)?;
+    require!(now <= dispute_window_end, ErrorCode::DisputeNotAllowed);
+
+    require!(
+        !donation_record_account.is_refunded && donation_record_account.amount > 0,
+        ErrorCode::DisputeNotAllowed
+    );
+    require!(
+        donation_record_account.current_flag(crowdfund_account.succeeded_at) == 0,
+        ErrorCode::AlreadyFlagged
+    );
+
+    donation_record_account.flagged_amount = donation_record_account.amount;
+    donation_record_account.flagged_at = crowdfund_account.succeeded_at;
+    crowdfund_account.flagged_amount = crowdfund_account.flagged_amount
+        .checked_add(donation_record_account.amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(CampaignFlagged {
+        campaign: crowdfund_account.key(),
+        donor: ctx.accounts.donor.key(),
+        amount: donation_record_account.amount,
+        flagged_amount: crowdfund_account.flagged_amount
+    });
+
+    let flagged_bps = crowdfund_account.flagged_amount as u128 * BPS_DENOMINATOR as u128;
+    let threshold = crowdfund_account.raised_amount as u128 * config.dispute_threshold_bps as u128;
+    if flagged_bps > threshold {
+        crowdfund_account.state = CampaignState::Disputed as u8;
+
+        emit!(CampaignDisputed {
+            campaign: crowdfund_account.key(),
+            flagged_amount: crowdfund_account.flagged_amount,
+            raised_amount: crowdfund_account.raised_amount
+        });
+    }
+
+    Ok(())
+}
+
+// 仲裁人放行则恢复成功并允许提款，否则众筹失败并开放退款
+pub fn proccess_resolve_dispute(ctx: Context<ResolveDispute>, release: bool) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Disputed), ErrorCode::DisputeNotAllowed);
+
+    if release {
+        crowdfund_account.state = CampaignState::Success as u8;
+    } else {
+        // 与取消相同，任何筹款模式都可以退款
+        crowdfund_account.state = CampaignState::Fail as u8;
+        crowdfund_account.is_cancelled = true;
+    }
+    crowdfund_account.is_dispute_resolved = true;
+
+    emit!(DisputeResolved {
+        campaign: crowdfund_account.key(),
+        arbiter: ctx.accounts.arbiter.key(),
+        released: release
+    });
+
+    Ok(())
+}
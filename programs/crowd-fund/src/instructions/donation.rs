@@ -1,29 +1,66 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, MintTo, TokenAccount, TokenInterface, TransferChecked, mint_to, transfer_checked}
+};
 
-use crate::{error::ErrorCode, state::{CampaignState, Crowdfund, DonationRecord}};
+use crate::{
+    error::ErrorCode,
+    instructions::{allowlist::verify_allowlist, blocklist::require_not_blocked, matching::transfer_match},
+    state::{CampaignState, Config, Crowdfund, DonationRecord, FundingModel, MatchingPool, ReferrerStats, Round, MAX_MEMO_LEN}
+};
 
 
 #[event]
 pub struct DonationMade {
     pub donor: Pubkey,
-    pub amount: u64
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub tier: Option<u8>,
+    pub campaign: Pubkey,
+    pub memo: Option<Vec<u8>>,
+    pub reference: Option<Pubkey>,
+    pub matched_amount: u64,
+    pub referrer: Option<Pubkey>
+}
+
+#[event]
+pub struct StretchGoalReached {
+    pub campaign: Pubkey,
+    pub index: u8,
+    pub target: u64,
+    pub description_hash: [u8; 32],
+    pub raised_amount: u64,
 }
 
 
 #[derive(Accounts)]
+#[instruction(amount: u64, tier: Option<u8>, memo: Option<Vec<u8>>, referrer: Option<Pubkey>)]
 pub struct InitDonationRecord<'info> {
+    // 支付捐赠记录租金
     #[account(mut)]
-    pub donor: Signer<'info>,
+    pub payer: Signer<'info>,
+
+    // 捐赠代币账户的所有者
+    pub token_authority: Signer<'info>,
+
+    // 捐赠记录归属人，退款与奖励都归其所有
+    pub beneficiary: SystemAccount<'info>,
 
     pub maker: SystemAccount<'info>,
 
     pub mint: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
     #[account(
         mut,
-        seeds = [maker.key().as_ref()],
-        bump
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
@@ -38,9 +75,9 @@ pub struct InitDonationRecord<'info> {
 
     #[account(
         init,
-        payer = donor,
+        payer = payer,
         space = 8 + DonationRecord::INIT_SPACE,
-        seeds = [donor.key().as_ref()],
+        seeds = [b"donation", crowdfund_account.key().as_ref(), beneficiary.key().as_ref()],
         bump
     )]
     pub donation_record_account: Account<'info, DonationRecord>,
@@ -48,43 +85,120 @@ pub struct InitDonationRecord<'info> {
     #[account(
         mut,
         associated_token::mint = mint,
-        associated_token::authority = donor,
+        associated_token::authority = token_authority,
         associated_token::token_program = token_program
     )]
     pub donation_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    // 仅当众筹启用了质押凭证代币时需要
+    #[account(
+        mut,
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub pledge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub pledge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // 众筹有赞助方配捐池时必须传入
+    #[account(
+        mut,
+        seeds = [b"matching_pool", crowdfund_account.key().as_ref()],
+        bump = matching_pool.bump
+    )]
+    pub matching_pool: Option<Account<'info, MatchingPool>>,
+
+    #[account(mut)]
+    pub matching_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // 计入二次方资助轮次时传入
+    #[account(mut)]
+    pub round: Option<Account<'info, Round>>,
+
+    // 传入推荐人时必须同时传入其统计账户
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ReferrerStats::INIT_SPACE,
+        seeds = [b"referrer", crowdfund_account.key().as_ref(), referrer.unwrap_or_default().as_ref()],
+        bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    /// CHECK: read-only Solana Pay style reference key, only used to locate the transaction off-chain
+    pub reference: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: blocklist entry for the donation beneficiary, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", beneficiary.key().as_ref()],
+        bump
+    )]
+    pub beneficiary_blocklist: UncheckedAccount<'info>,
+
+    /// CHECK: blocklist entry for the wallet funding the donation, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", token_authority.key().as_ref()],
+        bump
+    )]
+    pub token_authority_blocklist: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>
 }
 
 
-pub fn proccess_donation_record(ctx: Context<InitDonationRecord>, amount: u64) -> Result<()> {
+pub fn proccess_donation_record(
+    ctx: Context<InitDonationRecord>,
+    amount: u64,
+    tier: Option<u8>,
+    memo: Option<Vec<u8>>,
+    referrer: Option<Pubkey>,
+    allowlist_proof: Option<Vec<[u8; 32]>>
+) -> Result<()> {
     let crowdfund_account = &mut ctx.accounts.crowdfund_account;
     let donation_record_account = &mut ctx.accounts.donation_record_account;
 
-    // Check that donation amount is greater than zero
-    require!(amount > 0, ErrorCode::InvalidDonationAmount);
+    require!(memo.as_ref().map_or(0, |memo| memo.len()) <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+    require_not_blocked(&ctx.accounts.beneficiary_blocklist)?;
+    require_not_blocked(&ctx.accounts.token_authority_blocklist)?;
+
+    require!(
+        referrer.is_some() == ctx.accounts.referrer_stats.is_some()
+            && referrer != Some(ctx.accounts.beneficiary.key()),
+        ErrorCode::InvalidReferrer
+    );
+
+    // 白名单校验实际出资的签名者，而不是不需要签名的记录归属人
+    let allowlisted = verify_allowlist(
+        crowdfund_account,
+        &ctx.accounts.token_authority.key(),
+        allowlist_proof.as_deref()
+    )?;
 
     let now = Clock::get()?.unix_timestamp;
+    validate_donation(&ctx.accounts.config, crowdfund_account, amount, now, allowlisted)?;
 
-    if now < crowdfund_account.start_time {
-        return Err(ErrorCode::NoStared.into());
-    } else if now > crowdfund_account.end_time {
-        return Err(ErrorCode::CampaignExpired.into());
+    // 仅白名单众筹与提前访问窗口内，捐款记录只能归已验证的出资人本人，不能代未验证的钱包持仓
+    if crowdfund_account.allowlist_only || now < crowdfund_account.start_time {
+        require_keys_eq!(
+            ctx.accounts.beneficiary.key(),
+            ctx.accounts.token_authority.key(),
+            ErrorCode::NotAllowlisted
+        );
     }
 
-    // Check that the campaign is active using the helper method
-    match crowdfund_account.get_state() {
-        Some(CampaignState::Active) => {},
-        _ => return Err(ErrorCode::CampaignExpired.into()),
-    };
+    // 选择了奖励档位时，检查最低金额与剩余数量
+    if let Some(index) = tier {
+        crowdfund_account.claim_tier(index, amount)?;
+    }
 
     let cpi_accounts = TransferChecked {
         from: ctx.accounts.donation_token_account.to_account_info(),
         to: ctx.accounts.campaign_token_account.to_account_info(),
         mint: ctx.accounts.mint.to_account_info(),
-        authority: ctx.accounts.donor.to_account_info(),
+        authority: ctx.accounts.token_authority.to_account_info(),
     };
 
     let cpi_ctx = CpiContext::new(
@@ -94,27 +208,188 @@ pub fn proccess_donation_record(ctx: Context<InitDonationRecord>, amount: u64) -
 
     transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
 
-    crowdfund_account.raised_amount = crowdfund_account.raised_amount
-        .checked_add(amount)
-        .ok_or(ErrorCode::Overflow)?;
+    // 按 1:1 铸造质押凭证代币
+    if crowdfund_account.pledge_mint.is_some() {
+        let (Some(pledge_mint), Some(pledge_token_account)) = (
+            &ctx.accounts.pledge_mint,
+            &ctx.accounts.pledge_token_account
+        ) else {
+            return Err(ErrorCode::InvalidPledgeAccounts.into());
+        };
+        require!(pledge_token_account.mint == pledge_mint.key(), ErrorCode::InvalidPledgeAccounts);
 
-    if crowdfund_account.raised_amount >= crowdfund_account.target_amount {
-        msg!("raised_amount: {}", crowdfund_account.raised_amount);
-        crowdfund_account.state = CampaignState::Success as u8;
+        let maker_key = ctx.accounts.maker.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"campaign",
+            maker_key.as_ref(),
+            &[crowdfund_account.bump]
+        ]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: pledge_mint.to_account_info(),
+                to: pledge_token_account.to_account_info(),
+                authority: crowdfund_account.to_account_info(),
+            },
+            signer_seeds
+        );
+
+        mint_to(cpi_ctx, amount)?;
+    }
+
+    credit_donation(crowdfund_account, amount)?;
+
+    // 配捐单独记账，同样计入募集金额
+    let mut matched_amount = 0;
+    if let Some(pool_key) = crowdfund_account.matching_pool {
+        let (Some(matching_pool), Some(matching_vault)) = (
+            ctx.accounts.matching_pool.as_mut(),
+            ctx.accounts.matching_vault.as_ref()
+        ) else {
+            return Err(ErrorCode::InvalidMatchingPool.into());
+        };
+        require_keys_eq!(matching_pool.key(), pool_key, ErrorCode::InvalidMatchingPool);
+
+        matched_amount = transfer_match(
+            matching_pool,
+            matching_vault,
+            &ctx.accounts.campaign_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+            amount
+        )?;
+        if matched_amount > 0 {
+            credit_donation(crowdfund_account, matched_amount)?;
+            crowdfund_account.matched_amount = crowdfund_account.matched_amount
+                .checked_add(matched_amount)
+                .ok_or(ErrorCode::Overflow)?;
+        }
     }
 
     donation_record_account.amount = amount;
     donation_record_account.campaign = crowdfund_account.escrow_account;
-    donation_record_account.donor = ctx.accounts.donor.key();
+    donation_record_account.donor = ctx.accounts.beneficiary.key();
     donation_record_account.is_refunded = false;
+    donation_record_account.donated_at = now;
+    donation_record_account.bump = ctx.bumps.donation_record_account;
+    donation_record_account.tier = tier;
+    donation_record_account.matched_amount = matched_amount;
+    donation_record_account.referrer = referrer;
+
+    // 累计推荐人带来的捐款
+    if let (Some(referrer), Some(referrer_stats)) = (referrer, ctx.accounts.referrer_stats.as_mut()) {
+        referrer_stats.campaign = crowdfund_account.key();
+        referrer_stats.referrer = referrer;
+        referrer_stats.referred_amount = referrer_stats.referred_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        referrer_stats.donation_count = referrer_stats.donation_count
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        referrer_stats.bump = ctx.bumps.referrer_stats.ok_or(ErrorCode::InvalidReferrer)?;
+
+        crowdfund_account.referred_amount = crowdfund_account.referred_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+    }
+
+    // 轮次进行中时按平方根累计本次捐款
+    // 记录归属人无需签名，计入轮次时要求由其本人出资，避免一笔资金拆给多个虚构归属人放大权重
+    if let Some(round) = ctx.accounts.round.as_mut() {
+        require!(round.is_open(now), ErrorCode::RoundNotActive);
+        require_keys_eq!(
+            ctx.accounts.beneficiary.key(),
+            ctx.accounts.token_authority.key(),
+            ErrorCode::RoundFunderMismatch
+        );
+        donation_record_account.round = Some(round.key());
+        round.sync_contribution(&crowdfund_account.key(), donation_record_account)?;
+    }
 
     msg!("Donation of {} succeeded. Total raised: {}", amount, crowdfund_account.raised_amount);
 
 
     emit!(DonationMade {
-        donor: ctx.accounts.donor.key(),
-        amount
+        donor: ctx.accounts.beneficiary.key(),
+        funder: ctx.accounts.token_authority.key(),
+        amount,
+        tier,
+        campaign: crowdfund_account.key(),
+        memo,
+        reference: ctx.accounts.reference.as_ref().map(|reference| reference.key()),
+        matched_amount,
+        referrer
     });
 
     Ok(())
 }
+
+// 检查全局暂停、捐款金额、白名单、众筹时间窗口与状态
+pub fn validate_donation(
+    config: &Config,
+    crowdfund_account: &Crowdfund,
+    amount: u64,
+    now: i64,
+    allowlisted: bool
+) -> Result<()> {
+    require!(!config.paused, ErrorCode::ProgramPaused);
+
+    // Check that donation amount is greater than zero
+    require!(amount > 0, ErrorCode::InvalidDonationAmount);
+
+    if crowdfund_account.allowlist_only {
+        require!(allowlisted, ErrorCode::NotAllowlisted);
+    }
+
+    // 白名单捐赠人可以在公开开始前的提前访问窗口内捐款
+    if now < crowdfund_account.opens_at(allowlisted) {
+        return Err(ErrorCode::NoStared.into());
+    } else if now > crowdfund_account.end_time {
+        return Err(ErrorCode::CampaignExpired.into());
+    }
+
+    // 全有或全无模式达到目标后仍接受捐款直到结束，以便达成延伸目标
+    match crowdfund_account.get_state() {
+        Some(CampaignState::Active) | Some(CampaignState::Success) => {},
+        _ => return Err(ErrorCode::CampaignExpired.into()),
+    };
+
+    require!(!crowdfund_account.is_paused, ErrorCode::CampaignIsPaused);
+
+    Ok(())
+}
+
+// 累计募集金额，全有或全无模式达到目标即成功
+pub fn credit_donation(crowdfund_account: &mut Account<Crowdfund>, amount: u64) -> Result<()> {
+    crowdfund_account.raised_amount = crowdfund_account.raised_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    // 灵活众筹在结束前持续接受捐款，由 finalize 结算
+    if crowdfund_account.get_funding_model() == Some(FundingModel::AllOrNothing)
+        && crowdfund_account.raised_amount >= crowdfund_account.target_amount
+    {
+        msg!("raised_amount: {}", crowdfund_account.raised_amount);
+        crowdfund_account.mark_success(Clock::get()?.unix_timestamp);
+    }
+
+    // 每越过一个延伸目标发出一次事件
+    while let Some(goal) = crowdfund_account.stretch_goals.get(crowdfund_account.stretch_goals_reached as usize) {
+        if crowdfund_account.raised_amount < goal.target {
+            break;
+        }
+
+        emit!(StretchGoalReached {
+            campaign: crowdfund_account.key(),
+            index: crowdfund_account.stretch_goals_reached,
+            target: goal.target,
+            description_hash: goal.description_hash,
+            raised_amount: crowdfund_account.raised_amount
+        });
+
+        crowdfund_account.stretch_goals_reached += 1;
+    }
+
+    Ok(())
+}
@@ -1,18 +1,50 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
-use crate::state::{Crowdfund, CampaignState};
+use crate::{
+    error::ErrorCode,
+    instructions::matching::settle_matching_pool,
+    state::{Config, Crowdfund, CampaignState, FundingModel, MatchingPool}
+};
 
 #[derive(Accounts)]
 pub struct Finalize<'info> {
     pub make: SystemAccount<'info>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
     #[account(
         mut,
-        seeds = [make.key().as_ref()],
-        bump
+        seeds = [b"campaign", make.key().as_ref()],
+        bump = crowdfund_account.bump
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
+    // 以下账户仅在结算赞助方配捐池时需要
+    pub mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [b"matching_pool", crowdfund_account.key().as_ref()],
+        bump = matching_pool.bump
+    )]
+    pub matching_pool: Option<Account<'info, MatchingPool>>,
+
+    #[account(mut)]
+    pub matching_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub campaign_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut)]
+    pub sponsor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Option<Interface<'info, TokenInterface>>,
+
     pub system_program: Program<'info, System>
 }
 
@@ -20,17 +52,68 @@ pub fn proccess_finalize(ctx: Context<Finalize>) -> Result<()> {
     let crowdfund_account = &mut ctx.accounts.crowdfund_account;
     let now = Clock::get()?.unix_timestamp;
 
-    if now > crowdfund_account.end_time {
-        if crowdfund_account.raised_amount >= crowdfund_account.target_amount {
-            crowdfund_account.state = CampaignState::Success as u8;
-        } else {
-            crowdfund_account.state = CampaignState::Fail as u8;
+    // 暂停期间结束时间会被顺延，不能据此判定失败；暂停超过上限后不再顺延，可以结算
+    if crowdfund_account.is_paused {
+        let pause_expires_at = crowdfund_account
+            .pause_expires_at(ctx.accounts.config.max_pause_duration)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(now > pause_expires_at, ErrorCode::CampaignIsPaused);
+    }
+    // 争议中的众筹由仲裁人决定结果
+    require!(crowdfund_account.get_state() != Some(CampaignState::Disputed), ErrorCode::CampaignDisputed);
+
+    // 只有进行中的众筹才判定结果，已取消、失败或争议后的状态保持不变
+    if crowdfund_account.get_state() == Some(CampaignState::Active) {
+        if crowdfund_account.get_funding_model() == Some(FundingModel::KeepItAll) {
+            // 灵活众筹：结束后只要有募集金额即可提取
+            if now > crowdfund_account.end_time {
+                if crowdfund_account.raised_amount > 0 {
+                    crowdfund_account.mark_success(now);
+                } else {
+                    crowdfund_account.state = CampaignState::Fail as u8;
+                }
+            }
+        } else if now > crowdfund_account.end_time {
+            if crowdfund_account.raised_amount >= crowdfund_account.target_amount {
+                crowdfund_account.mark_success(now);
+            } else {
+                crowdfund_account.state = CampaignState::Fail as u8;
+            }
+        } else if crowdfund_account.raised_amount >= crowdfund_account.target_amount {
+            crowdfund_account.mark_success(now);
         }
-    } else {
-        if crowdfund_account.raised_amount >= crowdfund_account.target_amount {
-            crowdfund_account.state = CampaignState::Success as u8;
+    }
+
+    // 众筹结果不可再变时，退回配捐池剩余资金；失败时退回全部配捐
+    if let Some(matching_pool) = ctx.accounts.matching_pool.as_mut() {
+        if crowdfund_account.is_final(&ctx.accounts.config, now) && !matching_pool.is_settled {
+            let (
+                Some(mint),
+                Some(matching_vault),
+                Some(campaign_token_account),
+                Some(sponsor_token_account),
+                Some(token_program)
+            ) = (
+                &ctx.accounts.mint,
+                &ctx.accounts.matching_vault,
+                &ctx.accounts.campaign_token_account,
+                &ctx.accounts.sponsor_token_account,
+                &ctx.accounts.token_program
+            ) else {
+                return Err(ErrorCode::InvalidMatchingPool.into());
+            };
+
+            settle_matching_pool(
+                crowdfund_account,
+                matching_pool,
+                matching_vault,
+                campaign_token_account,
+                sponsor_token_account,
+                mint,
+                token_program
+            )?;
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
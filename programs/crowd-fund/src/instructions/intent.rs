@@ -0,0 +1,268 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked}
+};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::{blocklist::require_not_blocked, donation::{credit_donation, validate_donation}},
+    state::{Config, Crowdfund, DonationRecord, IntentNonce}
+};
+
+// Ed25519 程序指令数据布局：[签名数量 u8][填充 u8][偏移量结构 14 字节]...
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+
+
+#[event]
+pub struct IntentDonationMade {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub relayer: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+}
+
+
+#[derive(Accounts)]
+#[instruction(amount: u64, nonce: u64)]
+pub struct DonateWithIntent<'info> {
+    // 代付手续费与租金
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    // 离线签署捐款意向的捐赠者
+    pub donor: SystemAccount<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + DonationRecord::INIT_SPACE,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+
+    // 捐赠者需预先将众筹 PDA 设为该账户的代理
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = donor,
+        associated_token::token_program = token_program
+    )]
+    pub donation_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // 已使用的 nonce，重复提交时 init 失败
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + IntentNonce::INIT_SPACE,
+        seeds = [b"intent", donor.key().as_ref(), nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub intent_nonce: Account<'info, IntentNonce>,
+
+    /// CHECK: instructions sysvar, used to read the preceding ed25519 verification instruction
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// CHECK: blocklist entry for the donor, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", donor.key().as_ref()],
+        bump
+    )]
+    pub donor_blocklist: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+// 意向捐款只记录金额：不选择奖励档位、不触发配捐、不计入资助轮次、也没有推荐人
+pub fn proccess_donate_with_intent(
+    ctx: Context<DonateWithIntent>,
+    amount: u64,
+    nonce: u64,
+    expiry: i64
+) -> Result<()> {
+    require_not_blocked(&ctx.accounts.donor_blocklist)?;
+
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let donor_key = ctx.accounts.donor.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(now <= expiry, ErrorCode::IntentExpired);
+    validate_donation(&ctx.accounts.config, crowdfund_account, amount, now, false)?;
+    require!(crowdfund_account.pledge_mint.is_none(), ErrorCode::PledgeTokensEnabled);
+
+    let message = intent_message(&crowdfund_account.key(), amount, nonce, expiry);
+    verify_ed25519_instruction(&ctx.accounts.instructions_sysvar, &donor_key, &message)?;
+
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    // 众筹 PDA 以代理身份转出捐款
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.donation_token_account.to_account_info(),
+        to: ctx.accounts.campaign_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    credit_donation(crowdfund_account, amount)?;
+
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    donation_record_account.amount = amount;
+    donation_record_account.campaign = crowdfund_account.escrow_account;
+    donation_record_account.donor = donor_key;
+    donation_record_account.is_refunded = false;
+    donation_record_account.donated_at = now;
+    donation_record_account.bump = ctx.bumps.donation_record_account;
+    donation_record_account.tier = None;
+
+    let intent_nonce = &mut ctx.accounts.intent_nonce;
+    intent_nonce.donor = donor_key;
+    intent_nonce.nonce = nonce;
+    intent_nonce.used_at = now;
+
+    msg!("Intent donation of {} succeeded. Total raised: {}", amount, crowdfund_account.raised_amount);
+
+    emit!(IntentDonationMade {
+        campaign: crowdfund_account.key(),
+        donor: donor_key,
+        relayer: ctx.accounts.relayer.key(),
+        amount,
+        nonce
+    });
+
+    Ok(())
+}
+
+// 签名消息：campaign(32) || amount(u64 LE) || nonce(u64 LE) || expiry(i64 LE)
+pub fn intent_message(campaign: &Pubkey, amount: u64, nonce: u64, expiry: i64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(56);
+    message.extend_from_slice(campaign.as_ref());
+    message.extend_from_slice(&amount.to_le_bytes());
+    message.extend_from_slice(&nonce.to_le_bytes());
+    message.extend_from_slice(&expiry.to_le_bytes());
+    message
+}
+
+// 要求前一条指令为 ed25519 验签指令，且签名者与消息都与意向一致
+fn verify_ed25519_instruction(instructions_sysvar: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, ErrorCode::InvalidIntentSignature);
+
+    let ix = load_instruction_at_checked(current_index as usize - 1, instructions_sysvar)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidIntentSignature);
+
+    let data = &ix.data;
+    require!(
+        data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN && data[0] == 1,
+        ErrorCode::InvalidIntentSignature
+    );
+
+    let read_u16 = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+    let offsets = ED25519_HEADER_LEN;
+    let signature_instruction_index = read_u16(offsets + 2);
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let public_key_instruction_index = read_u16(offsets + 6);
+    let message_data_offset = read_u16(offsets + 8) as usize;
+    let message_data_size = read_u16(offsets + 10) as usize;
+    let message_instruction_index = read_u16(offsets + 12);
+
+    // 签名、公钥和消息都必须位于该验签指令自身的数据中
+    require!(
+        signature_instruction_index == u16::MAX
+            && public_key_instruction_index == u16::MAX
+            && message_instruction_index == u16::MAX,
+        ErrorCode::InvalidIntentSignature
+    );
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + ED25519_PUBKEY_LEN)
+        .ok_or(ErrorCode::InvalidIntentSignature)?;
+    let signed_message = data
+        .get(message_data_offset..message_data_offset + message_data_size)
+        .ok_or(ErrorCode::InvalidIntentSignature)?;
+
+    require!(
+        public_key == signer.as_ref() && signed_message == message,
+        ErrorCode::InvalidIntentSignature
+    );
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intent_message_layout() {
+        let campaign = Pubkey::new_from_array([7u8; 32]);
+        let message = intent_message(&campaign, 0x0102_0304_0506_0708, 42, -1);
+
+        assert_eq!(message.len(), 56);
+        assert_eq!(&message[..32], &[7u8; 32]);
+        assert_eq!(&message[32..40], &[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(&message[40..48], &[42, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&message[48..56], &[0xff; 8]);
+    }
+
+    #[test]
+    fn intent_message_binds_every_field() {
+        let campaign = Pubkey::new_from_array([7u8; 32]);
+        let message = intent_message(&campaign, 100, 1, 1_700_000_000);
+
+        assert_ne!(message, intent_message(&Pubkey::new_from_array([8u8; 32]), 100, 1, 1_700_000_000));
+        assert_ne!(message, intent_message(&campaign, 101, 1, 1_700_000_000));
+        assert_ne!(message, intent_message(&campaign, 100, 2, 1_700_000_000));
+        assert_ne!(message, intent_message(&campaign, 100, 1, 1_700_000_001));
+    }
+}
@@ -0,0 +1,266 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{error::ErrorCode, state::{CampaignState, Crowdfund, MatchingPool, BPS_DENOMINATOR}};
+
+
+#[event]
+pub struct MatchingPoolCreated {
+    pub campaign: Pubkey,
+    pub sponsor: Pubkey,
+    pub ratio_bps: u16,
+    pub cap: u64,
+    pub deposit: u64,
+}
+
+#[event]
+pub struct MatchingPoolSettled {
+    pub campaign: Pubkey,
+    pub sponsor: Pubkey,
+    pub returned: u64,
+}
+
+
+// 配捐池一经创建所有捐款都必须经过它，需要所有者共同签名同意
+#[derive(Accounts)]
+pub struct CreateMatchingPool<'info> {
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    pub owner: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + MatchingPool::INIT_SPACE,
+        seeds = [b"matching_pool", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub matching_pool: Account<'info, MatchingPool>,
+
+    #[account(
+        init,
+        payer = sponsor,
+        associated_token::mint = mint,
+        associated_token::authority = matching_pool,
+        associated_token::token_program = token_program
+    )]
+    pub matching_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = sponsor,
+        associated_token::token_program = token_program
+    )]
+    pub sponsor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+pub fn proccess_create_matching_pool(
+    ctx: Context<CreateMatchingPool>,
+    ratio_bps: u16,
+    cap: u64,
+    deposit: u64
+) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(crowdfund_account.matching_pool.is_none(), ErrorCode::InvalidMatchingPool);
+    require!(ratio_bps > 0 && ratio_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidMatchingPool);
+    require!(cap > 0 && deposit > 0, ErrorCode::InvalidMatchingPool);
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.sponsor_token_account.to_account_info(),
+        to: ctx.accounts.matching_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.sponsor.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts
+    );
+
+    transfer_checked(cpi_ctx, deposit, ctx.accounts.mint.decimals)?;
+
+    let matching_pool = &mut ctx.accounts.matching_pool;
+    crowdfund_account.matching_pool = Some(matching_pool.key());
+    matching_pool.campaign = crowdfund_account.key();
+    matching_pool.sponsor = ctx.accounts.sponsor.key();
+    matching_pool.vault = ctx.accounts.matching_vault.key();
+    matching_pool.ratio_bps = ratio_bps;
+    matching_pool.cap = cap;
+    matching_pool.matched_amount = 0;
+    matching_pool.is_settled = false;
+    matching_pool.bump = ctx.bumps.matching_pool;
+
+    emit!(MatchingPoolCreated {
+        campaign: crowdfund_account.key(),
+        sponsor: matching_pool.sponsor,
+        ratio_bps,
+        cap,
+        deposit
+    });
+
+    Ok(())
+}
+
+// 众筹结果确定后退回未使用的配捐池余额，以及托管账户中未计入募集金额的配捐
+// （失败时全部配捐都退回）
+pub fn settle_matching_pool<'info>(
+    crowdfund_account: &Account<'info, Crowdfund>,
+    matching_pool: &mut Account<'info, MatchingPool>,
+    matching_vault: &InterfaceAccount<'info, TokenAccount>,
+    campaign_token_account: &InterfaceAccount<'info, TokenAccount>,
+    sponsor_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>
+) -> Result<u64> {
+    require!(!matching_pool.is_settled, ErrorCode::InvalidMatchingPool);
+    require_keys_eq!(matching_pool.vault, matching_vault.key(), ErrorCode::InvalidMatchingPool);
+    require_keys_eq!(crowdfund_account.escrow_account, campaign_token_account.key(), ErrorCode::InvalidMatchingPool);
+    require!(
+        sponsor_token_account.owner == matching_pool.sponsor && sponsor_token_account.mint == mint.key(),
+        ErrorCode::InvalidMatchingPool
+    );
+
+    let escrow_return = match crowdfund_account.get_state() {
+        Some(CampaignState::Fail) => matching_pool.matched_amount,
+        Some(CampaignState::Success) => matching_pool.matched_amount.saturating_sub(crowdfund_account.matched_amount),
+        _ => return Err(ErrorCode::InvalidMatchingPool.into()),
+    };
+
+    if escrow_return > 0 {
+        let creator_key = crowdfund_account.creator;
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            b"campaign",
+            creator_key.as_ref(),
+            &[crowdfund_account.bump]
+        ]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: campaign_token_account.to_account_info(),
+                to: sponsor_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                authority: crowdfund_account.to_account_info(),
+            },
+            signer_seeds
+        );
+
+        transfer_checked(cpi_ctx, escrow_return, mint.decimals)?;
+    }
+
+    let vault_return = matching_vault.amount;
+    if vault_return > 0 {
+        let campaign_key = matching_pool.campaign;
+        let pool_seeds: &[&[&[u8]]] = &[&[
+            b"matching_pool",
+            campaign_key.as_ref(),
+            &[matching_pool.bump]
+        ]];
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: matching_vault.to_account_info(),
+                to: sponsor_token_account.to_account_info(),
+                mint: mint.to_account_info(),
+                authority: matching_pool.to_account_info(),
+            },
+            pool_seeds
+        );
+
+        transfer_checked(cpi_ctx, vault_return, mint.decimals)?;
+    }
+
+    matching_pool.is_settled = true;
+
+    let returned = escrow_return.checked_add(vault_return).ok_or(ErrorCode::Overflow)?;
+
+    emit!(MatchingPoolSettled {
+        campaign: crowdfund_account.key(),
+        sponsor: matching_pool.sponsor,
+        returned
+    });
+
+    Ok(returned)
+}
+
+// 从配捐池转入托管账户，返回实际配捐金额
+pub fn transfer_match<'info>(
+    matching_pool: &mut Account<'info, MatchingPool>,
+    matching_vault: &InterfaceAccount<'info, TokenAccount>,
+    campaign_token_account: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64
+) -> Result<u64> {
+    require_keys_eq!(matching_pool.vault, matching_vault.key(), ErrorCode::InvalidMatchingPool);
+
+    // 结算后余额已退回赞助方，成功后截止前的捐款照常接收，只是不再配捐
+    if matching_pool.is_settled {
+        return Ok(0);
+    }
+
+    let matched = matching_pool.match_for(amount, matching_vault.amount);
+    if matched == 0 {
+        return Ok(0);
+    }
+
+    let campaign_key = matching_pool.campaign;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"matching_pool",
+        campaign_key.as_ref(),
+        &[matching_pool.bump]
+    ]];
+
+    let cpi_accounts = TransferChecked {
+        from: matching_vault.to_account_info(),
+        to: campaign_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        authority: matching_pool.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, matched, mint.decimals)?;
+
+    matching_pool.matched_amount = matching_pool.matched_amount
+        .checked_add(matched)
+        .ok_or(ErrorCode::Overflow)?;
+
+    Ok(matched)
+}
@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{CampaignState, Crowdfund, MAX_METADATA_NAME_LEN, MAX_METADATA_URI_LEN}
+};
+
+
+#[event]
+pub struct CampaignMetadataUpdated {
+    pub campaign: Pubkey,
+    pub name: String,
+    pub metadata_uri: String,
+    pub category: u8,
+    pub image_hash: [u8; 32],
+}
+
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct UpdateCampaignMetadata<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized,
+        realloc = Crowdfund::space_with_name(name.len()),
+        realloc::payer = owner,
+        realloc::zero = false
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    pub system_program: Program<'info, System>
+}
+
+
+pub fn proccess_update_campaign_metadata(
+    ctx: Context<UpdateCampaignMetadata>,
+    name: String,
+    metadata_uri: String,
+    category: u8,
+    image_hash: [u8; 32]
+) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    // 设置了共同所有者时需走提案审批
+    require!(!crowdfund_account.requires_approval(), ErrorCode::ApprovalRequired);
+
+    apply_metadata(crowdfund_account, name, metadata_uri, category, image_hash)
+}
+
+pub fn apply_metadata(
+    crowdfund_account: &mut Account<Crowdfund>,
+    name: String,
+    metadata_uri: String,
+    category: u8,
+    image_hash: [u8; 32]
+) -> Result<()> {
+    validate_metadata(&name, &metadata_uri)?;
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+
+    crowdfund_account.name = name.clone();
+    crowdfund_account.metadata_uri = metadata_uri.clone();
+    crowdfund_account.category = category;
+    crowdfund_account.image_hash = image_hash;
+
+    emit!(CampaignMetadataUpdated {
+        campaign: crowdfund_account.key(),
+        name,
+        metadata_uri,
+        category,
+        image_hash
+    });
+
+    Ok(())
+}
+
+pub fn validate_metadata(name: &str, metadata_uri: &str) -> Result<()> {
+    require!(name.len() <= MAX_METADATA_NAME_LEN, ErrorCode::NameTooLong);
+    require!(metadata_uri.len() <= MAX_METADATA_URI_LEN, ErrorCode::MetadataUriTooLong);
+
+    Ok(())
+}
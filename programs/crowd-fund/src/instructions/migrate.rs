@@ -0,0 +1,293 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked
+    }
+};
+
+use crate::{error::ErrorCode, state::{Crowdfund, DonationRecord}};
+
+
+// 旧版本账户布局：Crowdfund 以 [owner] 为种子，DonationRecord 以 [donor] 为种子，
+// 两者没有前缀，同一个钱包既是发起人又是捐赠者时地址会冲突。
+// 迁移指令把旧账户中的数据和托管代币搬到带前缀的新地址，并关闭旧账户。
+#[derive(AnchorDeserialize)]
+pub struct LegacyCrowdfund {
+    pub owner: Pubkey,
+    pub escrow_account: Pubkey,
+    pub name: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub target_amount: u64,
+    pub raised_amount: u64,
+    pub state: u8,
+    pub is_withdrawals: bool,
+    pub merkle_root: [u8; 32],
+}
+
+#[derive(AnchorDeserialize)]
+pub struct LegacyDonationRecord {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub is_refunded: bool,
+}
+
+
+#[event]
+pub struct CampaignMigrated {
+    pub legacy_campaign: Pubkey,
+    pub campaign: Pubkey,
+    pub escrow_amount: u64,
+}
+
+#[event]
+pub struct DonationRecordMigrated {
+    pub legacy_record: Pubkey,
+    pub record: Pubkey,
+    pub amount: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct MigrateCampaign<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: legacy Crowdfund at the unprefixed address, validated and deserialized in the handler
+    #[account(
+        mut,
+        seeds = [owner.key().as_ref()],
+        bump
+    )]
+    pub legacy_crowdfund_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = legacy_crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub legacy_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Crowdfund::INIT_SPACE,
+        seeds = [b"campaign", owner.key().as_ref()],
+        bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+#[derive(Accounts)]
+pub struct MigrateDonationRecord<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    /// CHECK: legacy DonationRecord at the unprefixed address, validated and deserialized in the handler
+    #[account(
+        mut,
+        seeds = [donor.key().as_ref()],
+        bump
+    )]
+    pub legacy_donation_record_account: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = donor,
+        space = 8 + DonationRecord::INIT_SPACE,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+
+pub fn proccess_migrate_campaign(ctx: Context<MigrateCampaign>) -> Result<()> {
+    let legacy: LegacyCrowdfund = load_legacy(
+        &ctx.accounts.legacy_crowdfund_account,
+        Crowdfund::DISCRIMINATOR
+    )?;
+    let owner_key = ctx.accounts.owner.key();
+
+    require!(legacy.owner == owner_key, ErrorCode::Unauthorized);
+    require!(
+        legacy.escrow_account == ctx.accounts.legacy_token_account.key(),
+        ErrorCode::InvalidLegacyAccount
+    );
+
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    crowdfund_account.owner = legacy.owner;
+    crowdfund_account.creator = legacy.owner;
+    crowdfund_account.beneficiary = legacy.owner;
+    crowdfund_account.escrow_account = ctx.accounts.campaign_token_account.key();
+    crowdfund_account.name = legacy.name;
+    crowdfund_account.start_time = legacy.start_time;
+    crowdfund_account.end_time = legacy.end_time;
+    crowdfund_account.target_amount = legacy.target_amount;
+    crowdfund_account.raised_amount = legacy.raised_amount;
+    crowdfund_account.state = legacy.state;
+    crowdfund_account.is_withdrawals = legacy.is_withdrawals;
+    // 旧版提款是一次性全额提取
+    crowdfund_account.withdrawn_amount = if legacy.is_withdrawals { legacy.raised_amount } else { 0 };
+    crowdfund_account.merkle_root = legacy.merkle_root;
+    crowdfund_account.bump = ctx.bumps.crowdfund_account;
+
+    // 旧 PDA 签名，将托管代币全部转入新的托管账户
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        owner_key.as_ref(),
+        &[ctx.bumps.legacy_crowdfund_account]
+    ]];
+
+    let escrow_amount = ctx.accounts.legacy_token_account.amount;
+    if escrow_amount > 0 {
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.legacy_token_account.to_account_info(),
+            to: ctx.accounts.campaign_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            authority: ctx.accounts.legacy_crowdfund_account.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds
+        );
+
+        transfer_checked(cpi_ctx, escrow_amount, ctx.accounts.mint.decimals)?;
+    }
+
+    let cpi_accounts = CloseAccount {
+        account: ctx.accounts.legacy_token_account.to_account_info(),
+        destination: ctx.accounts.owner.to_account_info(),
+        authority: ctx.accounts.legacy_crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    close_account(cpi_ctx)?;
+
+    close_legacy_account(
+        &ctx.accounts.legacy_crowdfund_account.to_account_info(),
+        &ctx.accounts.owner.to_account_info()
+    )?;
+
+    emit!(CampaignMigrated {
+        legacy_campaign: ctx.accounts.legacy_crowdfund_account.key(),
+        campaign: ctx.accounts.crowdfund_account.key(),
+        escrow_amount
+    });
+
+    Ok(())
+}
+
+pub fn proccess_migrate_donation_record(ctx: Context<MigrateDonationRecord>) -> Result<()> {
+    let legacy: LegacyDonationRecord = load_legacy(
+        &ctx.accounts.legacy_donation_record_account,
+        DonationRecord::DISCRIMINATOR
+    )?;
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let mint_key = ctx.accounts.mint.key();
+    let token_program_key = ctx.accounts.token_program.key();
+
+    require!(legacy.donor == ctx.accounts.donor.key(), ErrorCode::Unauthorized);
+
+    // 旧记录中保存的是旧托管账户地址，由发起人和 mint 重新推导后比对
+    let (legacy_crowdfund_key, _) = Pubkey::find_program_address(
+        &[ctx.accounts.maker.key().as_ref()],
+        ctx.program_id
+    );
+    require!(
+        crowdfund_account.escrow_account
+            == get_associated_token_address_with_program_id(&crowdfund_account.key(), &mint_key, &token_program_key),
+        ErrorCode::InvalidLegacyAccount
+    );
+    require!(
+        legacy.campaign
+            == get_associated_token_address_with_program_id(&legacy_crowdfund_key, &mint_key, &token_program_key),
+        ErrorCode::InvalidLegacyAccount
+    );
+
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    donation_record_account.campaign = crowdfund_account.escrow_account;
+    donation_record_account.donor = legacy.donor;
+    donation_record_account.amount = legacy.amount;
+    donation_record_account.is_refunded = legacy.is_refunded;
+    donation_record_account.donated_at = 0;
+    donation_record_account.bump = ctx.bumps.donation_record_account;
+
+    close_legacy_account(
+        &ctx.accounts.legacy_donation_record_account.to_account_info(),
+        &ctx.accounts.donor.to_account_info()
+    )?;
+
+    emit!(DonationRecordMigrated {
+        legacy_record: ctx.accounts.legacy_donation_record_account.key(),
+        record: donation_record_account.key(),
+        amount: donation_record_account.amount
+    });
+
+    Ok(())
+}
+
+
+fn load_legacy<T: AnchorDeserialize>(account: &UncheckedAccount, discriminator: &[u8]) -> Result<T> {
+    require_keys_eq!(*account.owner, crate::ID, ErrorCode::InvalidLegacyAccount);
+
+    let data = account.try_borrow_data()?;
+    require!(
+        data.len() > discriminator.len() && data.starts_with(discriminator),
+        ErrorCode::InvalidLegacyAccount
+    );
+
+    T::deserialize(&mut &data[discriminator.len()..]).map_err(|_| ErrorCode::InvalidLegacyAccount.into())
+}
+
+fn close_legacy_account<'info>(account: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = account.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ErrorCode::Overflow)?;
+    **account.try_borrow_mut_lamports()? = 0;
+
+    account.assign(&System::id());
+    account.resize(0)?;
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::Crowdfund};
+
+
+#[event]
+pub struct OwnershipTransferProposed {
+    pub campaign: Pubkey,
+    pub owner: Pubkey,
+    pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    pub campaign: Pubkey,
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
+}
+
+
+#[derive(Accounts)]
+pub struct ProposeOwner<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwner<'info> {
+    pub new_owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+// 两步转移：新所有者确认后才生效，PDA 仍由创建者派生
+pub fn proccess_propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(
+        new_owner != crowdfund_account.owner && !crowdfund_account.co_owners.contains(&new_owner),
+        ErrorCode::InvalidOwner
+    );
+
+    crowdfund_account.pending_owner = Some(new_owner);
+
+    emit!(OwnershipTransferProposed {
+        campaign: crowdfund_account.key(),
+        owner: crowdfund_account.owner,
+        pending_owner: new_owner
+    });
+
+    Ok(())
+}
+
+pub fn proccess_accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let new_owner = ctx.accounts.new_owner.key();
+
+    require!(
+        crowdfund_account.pending_owner == Some(new_owner) && !crowdfund_account.co_owners.contains(&new_owner),
+        ErrorCode::InvalidOwner
+    );
+
+    let previous_owner = crowdfund_account.owner;
+    crowdfund_account.owner = new_owner;
+    crowdfund_account.pending_owner = None;
+
+    emit!(OwnershipTransferred {
+        campaign: crowdfund_account.key(),
+        previous_owner,
+        new_owner
+    });
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{CampaignState, Config, Crowdfund}};
+
+
+#[event]
+pub struct CampaignPaused {
+    pub campaign: Pubkey,
+    pub paused_at: i64,
+}
+
+#[event]
+pub struct CampaignResumed {
+    pub campaign: Pubkey,
+    pub paused_duration: i64,
+    pub end_time: i64,
+}
+
+
+#[derive(Accounts)]
+pub struct PauseCampaign<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+// 所有者可随时恢复，暂停超过上限后任何人都可以恢复
+#[derive(Accounts)]
+pub struct ResumeCampaign<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+pub fn proccess_pause_campaign(ctx: Context<PauseCampaign>) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(!crowdfund_account.is_paused, ErrorCode::CampaignIsPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    crowdfund_account.is_paused = true;
+    crowdfund_account.paused_at = now;
+
+    emit!(CampaignPaused {
+        campaign: crowdfund_account.key(),
+        paused_at: now
+    });
+
+    Ok(())
+}
+
+pub fn proccess_resume_campaign(ctx: Context<ResumeCampaign>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.is_paused, ErrorCode::CampaignNotPaused);
+
+    let now = Clock::get()?.unix_timestamp;
+    if ctx.accounts.caller.key() != crowdfund_account.owner {
+        let pause_expires_at = crowdfund_account
+            .pause_expires_at(config.max_pause_duration)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(now > pause_expires_at, ErrorCode::Unauthorized);
+    }
+
+    // 暂停期间不计入众筹时长，顺延结束时间，最多顺延暂停上限；
+    // 总时长已接近配置上限时只顺延剩余额度，恢复本身永远不会失败
+    let paused_duration = crowdfund_account.pause_credit(
+        now,
+        config.max_pause_duration,
+        config.max_campaign_duration
+    );
+    let end_time = crowdfund_account.end_time
+        .checked_add(paused_duration)
+        .ok_or(ErrorCode::Overflow)?;
+
+    crowdfund_account.end_time = end_time;
+    crowdfund_account.is_paused = false;
+    crowdfund_account.paused_at = 0;
+
+    msg!("Campaign resumed after {}s, new end time: {}", paused_duration, crowdfund_account.end_time);
+
+    emit!(CampaignResumed {
+        campaign: crowdfund_account.key(),
+        paused_duration,
+        end_time: crowdfund_account.end_time
+    });
+
+    Ok(())
+}
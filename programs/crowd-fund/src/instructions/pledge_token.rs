@@ -0,0 +1,283 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Burn, Mint, TokenAccount, TokenInterface, TransferChecked, burn, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::blocklist::is_blocked,
+    state::{CampaignState, Crowdfund, FrozenRedemption, FundingModel}
+};
+
+
+#[event]
+pub struct PledgeTokensEnabled {
+    pub campaign: Pubkey,
+    pub pledge_mint: Pubkey,
+}
+
+#[event]
+pub struct PledgeTokensRedeemed {
+    pub campaign: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RedemptionFrozen {
+    pub campaign: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub frozen_amount: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct EnablePledgeTokens<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    // 质押凭证与众筹代币精度一致，按 1:1 铸造
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump,
+        mint::decimals = mint.decimals,
+        mint::authority = crowdfund_account,
+        mint::token_program = token_program
+    )]
+    pub pledge_mint: InterfaceAccount<'info, Mint>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemPledgeTokens<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub pledge_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = pledge_mint,
+        token::authority = holder,
+        token::token_program = token_program
+    )]
+    pub holder_pledge_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = holder,
+        associated_token::mint = mint,
+        associated_token::authority = holder,
+        associated_token::token_program = token_program
+    )]
+    pub holder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: blocklist entry for the holder, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", holder.key().as_ref()],
+        bump
+    )]
+    pub holder_blocklist: UncheckedAccount<'info>,
+
+    // 仅在持有人被封禁，或已有冻结退款待领取时传入；
+    // 被封禁时记录已销毁凭证对应的冻结退款，解封后随下一次赎回一并支付
+    #[account(
+        init_if_needed,
+        payer = holder,
+        space = 8 + FrozenRedemption::INIT_SPACE,
+        seeds = [b"frozen_redemption", crowdfund_account.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub frozen_redemption: Option<Account<'info, FrozenRedemption>>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+pub fn proccess_enable_pledge_tokens(ctx: Context<EnablePledgeTokens>) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(
+        crowdfund_account.pledge_mint.is_none() && crowdfund_account.raised_amount == 0,
+        ErrorCode::PledgeTokensLocked
+    );
+
+    crowdfund_account.pledge_mint = Some(ctx.accounts.pledge_mint.key());
+
+    emit!(PledgeTokensEnabled {
+        campaign: crowdfund_account.key(),
+        pledge_mint: ctx.accounts.pledge_mint.key()
+    });
+
+    Ok(())
+}
+
+pub fn proccess_redeem_pledge_tokens(ctx: Context<RedeemPledgeTokens>, amount: u64) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let frozen_amount = ctx.accounts.frozen_redemption.as_ref().map_or(0, |frozen| frozen.amount);
+
+    // 解封后可以只领取之前冻结的退款
+    require!(amount > 0 || frozen_amount > 0, ErrorCode::InvalidDonationAmount);
+    require!(
+        crowdfund_account.pledge_mint == Some(ctx.accounts.pledge_mint.key()),
+        ErrorCode::PledgeTokensNotEnabled
+    );
+    require!(
+        crowdfund_account.get_state() == Some(CampaignState::Fail)
+            && (crowdfund_account.get_funding_model() == Some(FundingModel::AllOrNothing)
+                || crowdfund_account.is_cancelled),
+        ErrorCode::RefundNotAllowed
+    );
+
+    // 持有人销毁凭证，按 1:1 从托管账户取回代币
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Burn {
+            mint: ctx.accounts.pledge_mint.to_account_info(),
+            from: ctx.accounts.holder_pledge_token_account.to_account_info(),
+            authority: ctx.accounts.holder.to_account_info(),
+        }
+    );
+
+    if amount > 0 {
+        burn(cpi_ctx, amount)?;
+    }
+
+    // 与退款一致：被封禁的持有人不付款，凭证照常销毁以免转移给他人赎回，金额冻结在托管账户中
+    if is_blocked(&ctx.accounts.holder_blocklist) {
+        require!(amount > 0, ErrorCode::Blocked);
+        let Some(frozen_redemption) = ctx.accounts.frozen_redemption.as_mut() else {
+            return Err(ErrorCode::InvalidPledgeAccounts.into());
+        };
+
+        frozen_redemption.campaign = crowdfund_account.key();
+        frozen_redemption.holder = ctx.accounts.holder.key();
+        frozen_redemption.amount = frozen_redemption.amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        frozen_redemption.bump = ctx.bumps.frozen_redemption.ok_or(ErrorCode::InvalidPledgeAccounts)?;
+
+        emit!(RedemptionFrozen {
+            campaign: crowdfund_account.key(),
+            holder: ctx.accounts.holder.key(),
+            amount,
+            frozen_amount: frozen_redemption.amount
+        });
+
+        return Ok(());
+    }
+
+    let payout = amount.checked_add(frozen_amount).ok_or(ErrorCode::Overflow)?;
+
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.campaign_token_account.to_account_info(),
+        to: ctx.accounts.holder_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, payout, ctx.accounts.mint.decimals)?;
+
+    emit!(PledgeTokensRedeemed {
+        campaign: crowdfund_account.key(),
+        holder: ctx.accounts.holder.key(),
+        amount: payout
+    });
+
+    // 冻结记录已结清，关闭并退还租金
+    if let Some(frozen_redemption) = ctx.accounts.frozen_redemption.as_ref() {
+        frozen_redemption.close(ctx.accounts.holder.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+// 启用质押凭证后，撤回或退出的支持者需销毁等额凭证
+pub fn burn_pledge_tokens<'info>(
+    crowdfund_account: &Crowdfund,
+    pledge_mint: Option<&InterfaceAccount<'info, Mint>>,
+    pledge_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+    owner: &Signer<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount: u64
+) -> Result<()> {
+    if crowdfund_account.pledge_mint.is_none() {
+        return Ok(());
+    }
+
+    let (Some(pledge_mint), Some(pledge_token_account)) = (pledge_mint, pledge_token_account) else {
+        return Err(ErrorCode::InvalidPledgeAccounts.into());
+    };
+    require!(
+        crowdfund_account.pledge_mint == Some(pledge_mint.key())
+            && pledge_token_account.mint == pledge_mint.key()
+            && pledge_token_account.owner == owner.key(),
+        ErrorCode::InvalidPledgeAccounts
+    );
+
+    let cpi_ctx = CpiContext::new(
+        token_program.to_account_info(),
+        Burn {
+            mint: pledge_mint.to_account_info(),
+            from: pledge_token_account.to_account_info(),
+            authority: owner.to_account_info(),
+        }
+    );
+
+    burn(cpi_ctx, amount)
+}
@@ -0,0 +1,191 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::blocklist::require_not_blocked,
+    state::{referral_share, CampaignState, Config, Crowdfund, DonationRecord, ReferrerStats, BPS_DENOMINATOR}
+};
+
+
+#[event]
+pub struct ReferralShareUpdated {
+    pub campaign: Pubkey,
+    pub referral_share_bps: u16,
+}
+
+#[event]
+pub struct ReferralRewardClaimed {
+    pub campaign: Pubkey,
+    pub referrer: Pubkey,
+    pub referred_amount: u64,
+    pub reward: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct SetReferralShare<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralReward<'info> {
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"referrer", crowdfund_account.key().as_ref(), referrer.key().as_ref()],
+        bump = referrer_stats.bump
+    )]
+    pub referrer_stats: Account<'info, ReferrerStats>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = referrer,
+        associated_token::mint = mint,
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program
+    )]
+    pub referrer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: blocklist entry for the referrer, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_blocklist: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+// 推荐比例只能在开放捐款（含白名单提前访问）前设置，推荐人按开始时公布的比例获得奖励
+pub fn proccess_set_referral_share(ctx: Context<SetReferralShare>, referral_share_bps: u16) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(now < crowdfund_account.opens_at(true), ErrorCode::ReferralShareLocked);
+    require!(referral_share_bps as u64 <= BPS_DENOMINATOR, ErrorCode::InvalidConfig);
+
+    crowdfund_account.referral_share_bps = referral_share_bps;
+
+    emit!(ReferralShareUpdated {
+        campaign: crowdfund_account.key(),
+        referral_share_bps
+    });
+
+    Ok(())
+}
+
+pub fn proccess_claim_referral_reward(ctx: Context<ClaimReferralReward>) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let referrer_stats = &ctx.accounts.referrer_stats;
+
+    require_not_blocked(&ctx.accounts.referrer_blocklist)?;
+
+    // 成功结果确定后推荐金额不再变化，才能按比例领取
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        crowdfund_account.is_final_success(&ctx.accounts.config, now),
+        ErrorCode::ReferralNotClaimable
+    );
+    require!(!referrer_stats.is_claimed, ErrorCode::ReferralNotClaimable);
+
+    let reward = referral_share(referrer_stats.referred_amount, crowdfund_account.referral_share_bps);
+    require!(reward > 0, ErrorCode::ReferralNotClaimable);
+
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.campaign_token_account.to_account_info(),
+        to: ctx.accounts.referrer_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, reward, ctx.accounts.mint.decimals)?;
+
+    let referrer_stats = &mut ctx.accounts.referrer_stats;
+    referrer_stats.is_claimed = true;
+
+    emit!(ReferralRewardClaimed {
+        campaign: crowdfund_account.key(),
+        referrer: referrer_stats.referrer,
+        referred_amount: referrer_stats.referred_amount,
+        reward
+    });
+
+    Ok(())
+}
+
+// 捐款减少时同步扣减推荐人统计
+pub fn release_referral(
+    crowdfund_account: &mut Account<Crowdfund>,
+    referrer_stats: Option<&mut Account<ReferrerStats>>,
+    donation_record_account: &DonationRecord,
+    amount: u64
+) -> Result<()> {
+    let Some(referrer) = donation_record_account.referrer else {
+        return Ok(());
+    };
+    let Some(referrer_stats) = referrer_stats else {
+        return Err(ErrorCode::InvalidReferrer.into());
+    };
+    require_keys_eq!(referrer_stats.referrer, referrer, ErrorCode::InvalidReferrer);
+    require_keys_eq!(referrer_stats.campaign, crowdfund_account.key(), ErrorCode::InvalidReferrer);
+
+    referrer_stats.referred_amount = referrer_stats.referred_amount.saturating_sub(amount);
+    crowdfund_account.referred_amount = crowdfund_account.referred_amount.saturating_sub(amount);
+
+    Ok(())
+}
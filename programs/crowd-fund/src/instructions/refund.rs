@@ -1,12 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken, 
+    token_2022::Token2022,
     token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
 };
 
 use crate::{
     error::ErrorCode, 
-    state::{Crowdfund, DonationRecord, CampaignState}
+    instructions::{badge::burn_backer_badge, blocklist::is_blocked},
+    state::{Crowdfund, DonationRecord, CampaignState, FundingModel}
 };
 
 
@@ -17,6 +19,13 @@ pub struct RefundMade {
     pub amount: u64
 }
 
+#[event]
+pub struct RefundFrozen {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64
+}
+
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
@@ -28,15 +37,16 @@ pub struct Refund<'info> {
     pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
-        seeds = [weekly_planner.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"campaign", weekly_planner.key().as_ref()],
+        bump = crowdfund_account.bump
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
     #[account(
         mut,
-        seeds = [donor.key().as_ref()],
-        bump
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
     )]
     pub donation_record_account: Account<'info, DonationRecord>,
 
@@ -56,9 +66,28 @@ pub struct Refund<'info> {
     )]
     pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    // 仅当捐赠记录铸造过支持者徽章时需要
+    #[account(
+        mut,
+        seeds = [b"badge", donation_record_account.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub badge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: blocklist entry for the donor, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", donor.key().as_ref()],
+        bump
+    )]
+    pub donor_blocklist: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>
+    pub token_program: Interface<'info, TokenInterface>,
+    pub token_2022_program: Option<Program<'info, Token2022>>
 }
 
 pub fn proccess_refund(ctx: Context<Refund>) -> Result<()> {
@@ -68,6 +97,14 @@ pub fn proccess_refund(ctx: Context<Refund>) -> Result<()> {
         crowdfund_account.get_state() == Some(CampaignState::Fail),
         ErrorCode::RefundNotAllowed
     );
+    // 灵活众筹不支持退款，除非被取消
+    require!(
+        crowdfund_account.get_funding_model() == Some(FundingModel::AllOrNothing) || crowdfund_account.is_cancelled,
+        ErrorCode::RefundNotAllowed
+    );
+
+    // 启用质押凭证后，资金随代币流转，只能通过赎回代币退款
+    require!(crowdfund_account.pledge_mint.is_none(), ErrorCode::PledgeTokensEnabled);
 
     let donation_record_account = &mut ctx.accounts.donation_record_account;
     require!(
@@ -75,10 +112,26 @@ pub fn proccess_refund(ctx: Context<Refund>) -> Result<()> {
         ErrorCode::AlreadyRefunded
     );
 
+    // 被封禁的捐赠人不退款，资金冻结在托管账户中，解封后可再次退款
+    if is_blocked(&ctx.accounts.donor_blocklist) {
+        require!(!donation_record_account.is_frozen, ErrorCode::Blocked);
+        donation_record_account.is_frozen = true;
+
+        emit!(RefundFrozen {
+            campaign: ctx.accounts.crowdfund_account.key(),
+            donor: ctx.accounts.donor.key(),
+            amount: donation_record_account.amount
+        });
+
+        return Ok(());
+    }
+    donation_record_account.is_frozen = false;
+
     let weekly_planner_key = ctx.accounts.weekly_planner.key();
     let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
         weekly_planner_key.as_ref(),
-        &[ctx.bumps.crowdfund_account]
+        &[ctx.accounts.crowdfund_account.bump]
     ]];
 
     let cpi_accounts = TransferChecked {
@@ -98,6 +151,20 @@ pub fn proccess_refund(ctx: Context<Refund>) -> Result<()> {
 
     donation_record_account.is_refunded = true;
 
+    burn_backer_badge(
+        &ctx.accounts.crowdfund_account,
+        donation_record_account,
+        ctx.accounts.badge_mint.as_ref(),
+        ctx.accounts.badge_token_account.as_ref(),
+        ctx.accounts.token_2022_program.as_ref(),
+        ctx.accounts.donor.to_account_info()
+    )?;
+
+    // 释放奖励档位名额
+    if let Some(index) = donation_record_account.tier.take() {
+        ctx.accounts.crowdfund_account.release_tier(index);
+    }
+
     emit!(RefundMade {
         refunder: ctx.accounts.weekly_planner.key(),
         payee: ctx.accounts.donor.key(),
@@ -1,8 +1,15 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::hash;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+use anchor_spl::token_interface::{Mint, TokenAccount};
 
-use crate::{state::{CampaignState, Crowdfund, DonationRecord}, error::ErrorCode};
+use crate::{
+    error::ErrorCode,
+    instructions::blocklist::require_not_blocked,
+    state::{CampaignState, Config, Crowdfund, DonationRecord, HolderClaim}
+};
+
+// 持有人叶子的域前缀，避免与捐赠者叶子 "{donor}-{amount}" 冲突
+pub const HOLDER_LEAF_PREFIX: &str = "holder:";
 
 
 #[derive(Accounts)]
@@ -14,15 +21,21 @@ pub struct RewardClaim<'info> {
     // pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(
-        seeds = [maker.key().as_ref()],
-        bump
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
     #[account(
         mut,
-        seeds = [donor.key().as_ref()],
-        bump
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
     )]
     pub donation_record_account: Account<'info, DonationRecord>,
 
@@ -34,16 +47,79 @@ pub struct RewardClaim<'info> {
     // )]
     // pub donor_mint_account: InterfaceAccount<'info, TokenAccount>,
 
+    /// CHECK: blocklist entry for the donor, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", donor.key().as_ref()],
+        bump
+    )]
+    pub donor_blocklist: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     // pub associated_token_program: Program<'info, AssociatedToken>,
     // pub token_program: Interface<'info, TokenInterface>
 }
 
 
+#[derive(Accounts)]
+pub struct HolderRewardClaim<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub pledge_mint: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: blocklist entry for the holder, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", holder.key().as_ref()],
+        bump
+    )]
+    pub holder_blocklist: UncheckedAccount<'info>,
+
+    #[account(
+        token::mint = pledge_mint,
+        token::authority = holder
+    )]
+    pub holder_pledge_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // 每个持有人只能领取一次，重复领取时 init 失败
+    #[account(
+        init,
+        payer = holder,
+        space = 8 + HolderClaim::INIT_SPACE,
+        seeds = [b"holder_claim", crowdfund_account.key().as_ref(), holder.key().as_ref()],
+        bump
+    )]
+    pub holder_claim: Account<'info, HolderClaim>,
+
+    pub system_program: Program<'info, System>
+}
+
+
 pub fn proccess_reward_claim(ctx: Context<RewardClaim>, proof: Vec<[u8; 32]>) -> Result<()> {
+    require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+    require_not_blocked(&ctx.accounts.donor_blocklist)?;
+
     let crowdfund_account = &ctx.accounts.crowdfund_account;
     // 判断众筹是否成功
     require!(crowdfund_account.state == CampaignState::Success as u8, ErrorCode::CampaignNotSuccessful);
+    // 启用质押凭证后仓位随凭证转移，只能由持有人通过 holder_reward_claim 领取，避免同一仓位领取两次
+    require!(crowdfund_account.pledge_mint.is_none(), ErrorCode::PledgeTokensEnabled);
 
     let donation_record = &mut ctx.accounts.donation_record_account;
     msg!("donor amount: {}", donation_record.amount);
@@ -53,6 +129,46 @@ pub fn proccess_reward_claim(ctx: Context<RewardClaim>, proof: Vec<[u8; 32]>) ->
     let leaf = hash::hash(leaf_input.as_bytes()).to_bytes();
     
     // 使用proof 验证 Merkle Root
+    let computed_root = compute_merkle_root(leaf, &proof);
+
+    msg!("computed_root: {:?}", computed_root);
+    msg!("merkle_root: {:?}", crowdfund_account.merkle_root);
+
+    // 与链上存储的 Merkle Root 进行比较
+    require!(computed_root == crowdfund_account.merkle_root, ErrorCode::InvalidMerkleProof);
+
+    Ok(())
+}
+
+pub fn proccess_holder_reward_claim(ctx: Context<HolderRewardClaim>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+    require!(!ctx.accounts.config.paused, ErrorCode::ProgramPaused);
+    require_not_blocked(&ctx.accounts.holder_blocklist)?;
+
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    require!(crowdfund_account.state == CampaignState::Success as u8, ErrorCode::CampaignNotSuccessful);
+
+    // 快照中的持仓仍需在持有人账户中
+    require!(ctx.accounts.holder_pledge_token_account.amount >= amount, ErrorCode::InsufficientPledge);
+
+    // 叶子节点："holder:{holder}-{amount}"
+    let leaf_input = format!("{}{}-{}", HOLDER_LEAF_PREFIX, ctx.accounts.holder.key(), amount);
+    let leaf = hash::hash(leaf_input.as_bytes()).to_bytes();
+
+    let computed_root = compute_merkle_root(leaf, &proof);
+    require!(computed_root == crowdfund_account.merkle_root, ErrorCode::InvalidMerkleProof);
+
+    let holder_claim = &mut ctx.accounts.holder_claim;
+    holder_claim.campaign = crowdfund_account.key();
+    holder_claim.holder = ctx.accounts.holder.key();
+    holder_claim.amount = amount;
+    holder_claim.claimed_at = Clock::get()?.unix_timestamp;
+    holder_claim.bump = ctx.bumps.holder_claim;
+
+    Ok(())
+}
+
+// 按排序后的兄弟节点逐层哈希，得到 Merkle Root
+pub fn compute_merkle_root(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
     let mut computed_root = leaf;
     for sibling in proof.iter() {
         let mut combined = Vec::with_capacity(64);
@@ -65,12 +181,5 @@ pub fn proccess_reward_claim(ctx: Context<RewardClaim>, proof: Vec<[u8; 32]>) ->
         }
         computed_root = hash::hash(&combined).to_bytes();
     }
-
-    msg!("computed_root: {:?}", computed_root);
-    msg!("merkle_root: {:?}", crowdfund_account.merkle_root);
-
-    // 与链上存储的 Merkle Root 进行比较
-    require!(computed_root == crowdfund_account.merkle_root, ErrorCode::InvalidMerkleProof);
-
-    Ok(())
+    computed_root
 }
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{CampaignState, Crowdfund, RewardTier, MAX_REWARD_TIERS}};
+
+
+#[event]
+pub struct RewardTierAdded {
+    pub campaign: Pubkey,
+    pub index: u8,
+    pub min_pledge: u64,
+    pub max_quantity: u32,
+}
+
+
+#[derive(Accounts)]
+pub struct AddRewardTier<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+pub fn proccess_add_reward_tier(ctx: Context<AddRewardTier>, min_pledge: u64, max_quantity: u32) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(min_pledge > 0 && max_quantity > 0, ErrorCode::InvalidRewardTier);
+    require!(crowdfund_account.reward_tiers.len() < MAX_REWARD_TIERS, ErrorCode::TooManyRewardTiers);
+
+    crowdfund_account.reward_tiers.push(RewardTier {
+        min_pledge,
+        max_quantity,
+        claimed: 0,
+    });
+
+    emit!(RewardTierAdded {
+        campaign: crowdfund_account.key(),
+        index: (crowdfund_account.reward_tiers.len() - 1) as u8,
+        min_pledge,
+        max_quantity
+    });
+
+    Ok(())
+}
@@ -0,0 +1,450 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::donation::credit_donation,
+    state::{
+        quadratic_weight, round_share, CampaignState, Config, Crowdfund, DonationRecord, Round, RoundCampaign,
+        MAX_ROUND_CAMPAIGNS
+    }
+};
+
+
+#[event]
+pub struct RoundCreated {
+    pub round: Pubkey,
+    pub round_id: u64,
+    pub mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct RoundFunded {
+    pub round: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RoundCampaignAdded {
+    pub round: Pubkey,
+    pub campaign: Pubkey,
+}
+
+#[event]
+pub struct RoundSettled {
+    pub round: Pubkey,
+    pub pot: u64,
+    pub distributed: u64,
+}
+
+#[event]
+pub struct RoundSwept {
+    pub round: Pubkey,
+    pub admin: Pubkey,
+    pub amount: u64,
+}
+
+
+#[derive(Accounts)]
+#[instruction(round_id: u64)]
+pub struct CreateRound<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Round::INIT_SPACE,
+        seeds = [b"round", round_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = round,
+        associated_token::token_program = token_program
+    )]
+    pub round_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+#[derive(Accounts)]
+pub struct FundRound<'info> {
+    pub funder: Signer<'info>,
+
+    #[account(address = round.mint @ ErrorCode::InvalidRound)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"round", round.round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        address = round.vault @ ErrorCode::InvalidRound
+    )]
+    pub round_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = funder,
+        associated_token::token_program = token_program
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+#[derive(Accounts)]
+pub struct AddRoundCampaign<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"round", round.round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(address = crowdfund_account.escrow_account @ ErrorCode::InvalidRound)]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+// remaining_accounts 按轮次中的众筹顺序依次传入 [众筹账户, 托管账户]
+#[derive(Accounts)]
+pub struct SettleRound<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = round.mint @ ErrorCode::InvalidRound)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"round", round.round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        address = round.vault @ ErrorCode::InvalidRound
+    )]
+    pub round_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>
+}
+
+// 结算后奖池中未分配的部分（全部众筹失败时的整个奖池，以及按比例分配的舍入余数）退回管理员
+#[derive(Accounts)]
+pub struct SweepRound<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(address = round.mint @ ErrorCode::InvalidRound)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"round", round.round_id.to_le_bytes().as_ref()],
+        bump = round.bump
+    )]
+    pub round: Account<'info, Round>,
+
+    #[account(
+        mut,
+        address = round.vault @ ErrorCode::InvalidRound
+    )]
+    pub round_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        associated_token::mint = mint,
+        associated_token::authority = admin,
+        associated_token::token_program = token_program
+    )]
+    pub admin_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>
+}
+
+
+pub fn proccess_create_round(
+    ctx: Context<CreateRound>,
+    round_id: u64,
+    start_time: i64,
+    end_time: i64
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(start_time < end_time, ErrorCode::InvalidTimeRange);
+    require!(end_time > now, ErrorCode::InvalidTimeRange);
+
+    let round = &mut ctx.accounts.round;
+    round.admin = ctx.accounts.admin.key();
+    round.round_id = round_id;
+    round.mint = ctx.accounts.mint.key();
+    round.vault = ctx.accounts.round_vault.key();
+    round.start_time = start_time;
+    round.end_time = end_time;
+    round.campaigns = Vec::new();
+    round.is_settled = false;
+    round.bump = ctx.bumps.round;
+
+    emit!(RoundCreated {
+        round: round.key(),
+        round_id,
+        mint: round.mint,
+        start_time,
+        end_time
+    });
+
+    Ok(())
+}
+
+pub fn proccess_fund_round(ctx: Context<FundRound>, amount: u64) -> Result<()> {
+    require!(amount > 0, ErrorCode::InvalidDonationAmount);
+    require!(!ctx.accounts.round.is_settled, ErrorCode::RoundNotActive);
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.funder_token_account.to_account_info(),
+        to: ctx.accounts.round_vault.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.funder.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts
+    );
+
+    transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    emit!(RoundFunded {
+        round: ctx.accounts.round.key(),
+        funder: ctx.accounts.funder.key(),
+        amount
+    });
+
+    Ok(())
+}
+
+pub fn proccess_add_round_campaign(ctx: Context<AddRoundCampaign>) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let round = &mut ctx.accounts.round;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(!round.is_settled && now <= round.end_time, ErrorCode::RoundNotActive);
+    require!(crowdfund_account.get_state() == Some(CampaignState::Active), ErrorCode::CampaignExpired);
+    require!(ctx.accounts.campaign_token_account.mint == round.mint, ErrorCode::InvalidRound);
+    require!(
+        round.campaigns.iter().all(|entry| entry.campaign != crowdfund_account.key()),
+        ErrorCode::InvalidRound
+    );
+    require!(round.campaigns.len() < MAX_ROUND_CAMPAIGNS, ErrorCode::TooManyRoundCampaigns);
+
+    round.campaigns.push(RoundCampaign {
+        campaign: crowdfund_account.key(),
+        escrow_account: crowdfund_account.escrow_account,
+        sum_sqrt: 0,
+        sum_contributions: 0,
+        match_amount: 0,
+    });
+
+    emit!(RoundCampaignAdded {
+        round: round.key(),
+        campaign: crowdfund_account.key()
+    });
+
+    Ok(())
+}
+
+// 二次方配捐：每个众筹的权重为 (Σ√c)² - Σc，按权重比例分配奖池
+// 所有众筹结果确定后才能结算，配捐只发给最终成功的众筹，避免之后失败时滞留在托管账户中
+pub fn proccess_settle_round<'info>(ctx: Context<'_, '_, 'info, 'info, SettleRound<'info>>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let config = &ctx.accounts.config;
+    let round = &ctx.accounts.round;
+
+    require!(!round.is_settled && now > round.end_time, ErrorCode::RoundNotSettleable);
+    require!(
+        ctx.remaining_accounts.len() == round.campaigns.len() * 2,
+        ErrorCode::InvalidRound
+    );
+
+    let mut crowdfunds = Vec::with_capacity(round.campaigns.len());
+    let mut weights = Vec::with_capacity(round.campaigns.len());
+    let mut total_weight: u128 = 0;
+
+    for (entry, accounts) in round.campaigns.iter().zip(ctx.remaining_accounts.chunks(2)) {
+        require_keys_eq!(accounts[0].key(), entry.campaign, ErrorCode::InvalidRound);
+        require_keys_eq!(accounts[1].key(), entry.escrow_account, ErrorCode::InvalidRound);
+
+        let crowdfund_account = Account::<Crowdfund>::try_from(&accounts[0])?;
+        require!(crowdfund_account.is_final(config, now), ErrorCode::RoundNotSettleable);
+
+        // 失败的众筹不参与分配
+        let weight = if crowdfund_account.is_final_success(config, now) {
+            quadratic_weight(entry.sum_sqrt, entry.sum_contributions).ok_or(ErrorCode::Overflow)?
+        } else {
+            0
+        };
+
+        total_weight = total_weight.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        weights.push(weight);
+        crowdfunds.push(crowdfund_account);
+    }
+
+    let pot = ctx.accounts.round_vault.amount;
+    let round_id = round.round_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"round",
+        round_id.as_ref(),
+        &[round.bump]
+    ]];
+
+    let mut distributed: u64 = 0;
+    let mut match_amounts = Vec::with_capacity(weights.len());
+
+    for ((crowdfund_account, weight), accounts) in crowdfunds
+        .iter_mut()
+        .zip(weights)
+        .zip(ctx.remaining_accounts.chunks(2))
+    {
+        let match_amount = round_share(pot, weight, total_weight).ok_or(ErrorCode::Overflow)?;
+
+        if match_amount > 0 {
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.round_vault.to_account_info(),
+                    to: accounts[1].clone(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.round.to_account_info(),
+                },
+                signer_seeds
+            );
+
+            transfer_checked(cpi_ctx, match_amount, ctx.accounts.mint.decimals)?;
+
+            credit_donation(crowdfund_account, match_amount)?;
+            crowdfund_account.exit(&crate::ID)?;
+
+            distributed = distributed.checked_add(match_amount).ok_or(ErrorCode::Overflow)?;
+        }
+
+        match_amounts.push(match_amount);
+    }
+
+    let round = &mut ctx.accounts.round;
+    for (entry, match_amount) in round.campaigns.iter_mut().zip(match_amounts) {
+        entry.match_amount = match_amount;
+    }
+    round.is_settled = true;
+
+    emit!(RoundSettled {
+        round: round.key(),
+        pot,
+        distributed
+    });
+
+    Ok(())
+}
+
+pub fn proccess_sweep_round(ctx: Context<SweepRound>) -> Result<()> {
+    let round = &ctx.accounts.round;
+
+    require!(round.is_settled, ErrorCode::RoundNotSettleable);
+
+    let amount = ctx.accounts.round_vault.amount;
+    require!(amount > 0, ErrorCode::InvalidDonationAmount);
+
+    let round_id = round.round_id.to_le_bytes();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"round",
+        round_id.as_ref(),
+        &[round.bump]
+    ]];
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        TransferChecked {
+            from: ctx.accounts.round_vault.to_account_info(),
+            to: ctx.accounts.admin_token_account.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            authority: ctx.accounts.round.to_account_info(),
+        },
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    emit!(RoundSwept {
+        round: round.key(),
+        admin: ctx.accounts.admin.key(),
+        amount
+    });
+
+    Ok(())
+}
+
+// 捐款记录计入了资助轮次时，金额变动需同步轮次统计
+pub fn sync_round_record(
+    round: Option<&mut Account<Round>>,
+    campaign: &Pubkey,
+    donation_record_account: &mut DonationRecord
+) -> Result<()> {
+    let Some(round_key) = donation_record_account.round else {
+        return Ok(());
+    };
+    let Some(round) = round else {
+        return Err(ErrorCode::InvalidRound.into());
+    };
+    require_keys_eq!(round.key(), round_key, ErrorCode::InvalidRound);
+
+    round.sync_contribution(campaign, donation_record_account)
+}
@@ -7,12 +7,13 @@ use crate::{state::{CampaignState, Crowdfund}, error::ErrorCode};
 #[derive(Accounts)]
 pub struct SetMerkleRoot<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
+    pub owner: Signer<'info>,
 
     #[account(
         mut,
-        seeds = [authority.key().as_ref()],
-        bump
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
@@ -22,6 +23,13 @@ pub struct SetMerkleRoot<'info> {
 
 pub fn proccess_merkle_root(ctx: Context<SetMerkleRoot>, merkle_root: [u8; 32]) -> Result<()> {
     let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    // 设置了共同所有者时需走提案审批
+    require!(!crowdfund_account.requires_approval(), ErrorCode::ApprovalRequired);
+
+    apply_merkle_root(crowdfund_account, merkle_root)
+}
+
+pub fn apply_merkle_root(crowdfund_account: &mut Crowdfund, merkle_root: [u8; 32]) -> Result<()> {
     // 判断众筹是否成功
     require!(crowdfund_account.state == CampaignState::Success as u8, ErrorCode::CampaignNotSuccessful);
 
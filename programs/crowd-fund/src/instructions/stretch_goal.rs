@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::{CampaignState, Crowdfund, StretchGoal, MAX_STRETCH_GOALS}};
+
+
+#[event]
+pub struct StretchGoalAdded {
+    pub campaign: Pubkey,
+    pub index: u8,
+    pub target: u64,
+    pub description_hash: [u8; 32],
+}
+
+
+#[derive(Accounts)]
+pub struct AddStretchGoal<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+}
+
+
+pub fn proccess_add_stretch_goal(
+    ctx: Context<AddStretchGoal>,
+    target: u64,
+    description_hash: [u8; 32]
+) -> Result<()> {
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    // 达到主目标后、结束之前仍可追加延伸目标
+    require!(
+        matches!(crowdfund_account.get_state(), Some(CampaignState::Active) | Some(CampaignState::Success))
+            && now <= crowdfund_account.end_time,
+        ErrorCode::CampaignExpired
+    );
+    require!(crowdfund_account.stretch_goals.len() < MAX_STRETCH_GOALS, ErrorCode::TooManyStretchGoals);
+
+    // 延伸目标按金额递增，且都高于主目标
+    let previous_target = crowdfund_account.stretch_goals
+        .last()
+        .map_or(crowdfund_account.target_amount, |goal| goal.target);
+    require!(target > previous_target, ErrorCode::InvalidStretchGoal);
+    require!(target > crowdfund_account.raised_amount, ErrorCode::InvalidStretchGoal);
+
+    crowdfund_account.stretch_goals.push(StretchGoal {
+        target,
+        description_hash,
+    });
+
+    emit!(StretchGoalAdded {
+        campaign: crowdfund_account.key(),
+        index: (crowdfund_account.stretch_goals.len() - 1) as u8,
+        target,
+        description_hash
+    });
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_2022::Token2022,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
+};
+
+use crate::{
+    error::ErrorCode,
+    instructions::{
+        badge::burn_backer_badge,
+        blocklist::require_not_blocked,
+        pledge_token::burn_pledge_tokens,
+        referral::release_referral,
+        round::sync_round_record
+    },
+    state::{CampaignState, Config, Crowdfund, DonationRecord, ReferrerStats, Round}
+};
+
+
+#[event]
+pub struct PledgeWithdrawn {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+
+#[derive(Accounts)]
+pub struct Unpledge<'info> {
+    #[account(mut)]
+    pub donor: Signer<'info>,
+
+    pub maker: SystemAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        seeds = [b"campaign", maker.key().as_ref()],
+        bump = crowdfund_account.bump
+    )]
+    pub crowdfund_account: Account<'info, Crowdfund>,
+
+    #[account(
+        mut,
+        seeds = [b"donation", crowdfund_account.key().as_ref(), donor.key().as_ref()],
+        bump = donation_record_account.bump
+    )]
+    pub donation_record_account: Account<'info, DonationRecord>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = donor,
+        associated_token::token_program = token_program
+    )]
+    pub donation_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = crowdfund_account,
+        associated_token::token_program = token_program
+    )]
+    pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    // 捐款计入了资助轮次时必须传入
+    #[account(mut)]
+    pub round: Option<Account<'info, Round>>,
+
+    // 捐款带有推荐人时必须传入
+    #[account(mut)]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
+    // 众筹启用了质押凭证时需要，按撤回金额销毁
+    #[account(
+        mut,
+        seeds = [b"pledge_mint", crowdfund_account.key().as_ref()],
+        bump
+    )]
+    pub pledge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub pledge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    // 仅当捐赠记录铸造过支持者徽章、且本次退回全部捐款时需要
+    #[account(
+        mut,
+        seeds = [b"badge", donation_record_account.key().as_ref()],
+        bump
+    )]
+    pub badge_mint: Option<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut)]
+    pub badge_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: blocklist entry for the donor, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", donor.key().as_ref()],
+        bump
+    )]
+    pub donor_blocklist: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_2022_program: Option<Program<'info, Token2022>>
+}
+
+
+pub fn proccess_unpledge(ctx: Context<Unpledge>, amount: u64) -> Result<()> {
+    let crowdfund_account = &ctx.accounts.crowdfund_account;
+    let donation_record_account = &ctx.accounts.donation_record_account;
+    let now = Clock::get()?.unix_timestamp;
+
+    require!(amount > 0, ErrorCode::InvalidDonationAmount);
+    require_not_blocked(&ctx.accounts.donor_blocklist)?;
+    require!(!donation_record_account.is_refunded, ErrorCode::AlreadyRefunded);
+    require!(amount <= donation_record_account.amount, ErrorCode::InsufficientPledge);
+    require!(now <= crowdfund_account.end_time, ErrorCode::CampaignExpired);
+    require!(!crowdfund_account.is_withdrawals, ErrorCode::UnpledgeNotAllowed);
+
+    match crowdfund_account.get_state() {
+        Some(CampaignState::Active) => {},
+        Some(CampaignState::Success) if !ctx.accounts.config.lock_pledges_on_success => {},
+        _ => return Err(ErrorCode::UnpledgeNotAllowed.into()),
+    };
+
+    let maker_key = ctx.accounts.maker.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"campaign",
+        maker_key.as_ref(),
+        &[crowdfund_account.bump]
+    ]];
+
+    let cpi_accounts = TransferChecked {
+        from: ctx.accounts.campaign_token_account.to_account_info(),
+        to: ctx.accounts.donation_token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.crowdfund_account.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        signer_seeds
+    );
+
+    transfer_checked(cpi_ctx, amount, ctx.accounts.mint.decimals)?;
+
+    burn_pledge_tokens(
+        &ctx.accounts.crowdfund_account,
+        ctx.accounts.pledge_mint.as_ref(),
+        ctx.accounts.pledge_token_account.as_ref(),
+        &ctx.accounts.donor,
+        &ctx.accounts.token_program,
+        amount
+    )?;
+
+    // 对应比例的配捐留在托管账户中，结算时退回赞助方
+    let matched = ctx.accounts.donation_record_account.matched_share(amount);
+    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
+    // 已标记的部分先从争议金额中扣除，避免离开的支持者继续计入阈值
+    crowdfund_account.release_flag(&mut ctx.accounts.donation_record_account, amount);
+    crowdfund_account.release_pledge(amount, matched).ok_or(ErrorCode::Overflow)?;
+
+    release_referral(
+        crowdfund_account,
+        ctx.accounts.referrer_stats.as_mut(),
+        &ctx.accounts.donation_record_account,
+        amount
+    )?;
+
+    let donation_record_account = &mut ctx.accounts.donation_record_account;
+    donation_record_account.amount -= amount;
+    donation_record_account.matched_amount -= matched;
+
+    // 剩余金额低于档位最低要求时，释放档位名额
+    if let Some(index) = donation_record_account.tier {
+        let min_pledge = crowdfund_account.reward_tiers
+            .get(index as usize)
+            .map_or(0, |tier| tier.min_pledge);
+        if donation_record_account.amount < min_pledge {
+            crowdfund_account.release_tier(index);
+            donation_record_account.tier = None;
+        }
+    }
+
+    sync_round_record(ctx.accounts.round.as_mut(), &crowdfund_account.key(), donation_record_account)?;
+
+    msg!("Unpledged {}. Total raised: {}", amount, crowdfund_account.raised_amount);
+
+    emit!(PledgeWithdrawn {
+        campaign: crowdfund_account.key(),
+        donor: ctx.accounts.donor.key(),
+        amount,
+        remaining: donation_record_account.amount
+    });
+
+    // 全部撤回后销毁徽章、关闭捐赠记录并退还租金，之后可以重新捐款
+    if donation_record_account.amount == 0 {
+        burn_backer_badge(
+            crowdfund_account,
+            donation_record_account,
+            ctx.accounts.badge_mint.as_ref(),
+            ctx.accounts.badge_token_account.as_ref(),
+            ctx.accounts.token_2022_program.as_ref(),
+            ctx.accounts.donor.to_account_info()
+        )?;
+        donation_record_account.close(ctx.accounts.donor.to_account_info())?;
+    }
+
+    Ok(())
+}
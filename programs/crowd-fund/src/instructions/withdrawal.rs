@@ -4,35 +4,52 @@ use anchor_spl::{
     token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}
 };
 
-use crate::{error::ErrorCode, state::{Crowdfund, CampaignState}};
+use crate::{
+    error::ErrorCode,
+    instructions::blocklist::require_not_blocked,
+    state::{Config, Crowdfund, CampaignState, FundingModel}
+};
 
 
 #[event]
 pub struct WithdrawMade {
     pub withdrawer: Pubkey,
-    pub amount: u64
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub withdrawn_amount: u64
 }
 
 
 #[derive(Accounts)]
 pub struct DonationWithdrawal<'info> {
     #[account(mut)]
-    pub withdrawer: Signer<'info>,
+    pub owner: Signer<'info>,
 
     pub mint: InterfaceAccount<'info, Mint>,
 
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
     #[account(
         mut,
-        seeds = [withdrawer.key().as_ref()],
-        bump
+        seeds = [b"campaign", crowdfund_account.creator.as_ref()],
+        bump = crowdfund_account.bump,
+        has_one = owner @ ErrorCode::Unauthorized
     )]
     pub crowdfund_account: Account<'info, Crowdfund>,
 
+    /// CHECK: must be the beneficiary recorded on the campaign
+    #[account(address = crowdfund_account.beneficiary @ ErrorCode::Unauthorized)]
+    pub beneficiary: UncheckedAccount<'info>,
+
     #[account(
         init_if_needed,
-        payer = withdrawer,
+        payer = owner,
         associated_token::mint = mint,
-        associated_token::authority = withdrawer,
+        associated_token::authority = beneficiary,
         associated_token::token_program = token_program
     )]
     pub withdraw_token_account: InterfaceAccount<'info, TokenAccount>,
@@ -45,54 +62,134 @@ pub struct DonationWithdrawal<'info> {
     )]
     pub campaign_token_account: InterfaceAccount<'info, TokenAccount>,
 
+    /// CHECK: blocklist entry for the campaign beneficiary, holds no data unless the wallet is blocked
+    #[account(
+        seeds = [b"blocked", crowdfund_account.beneficiary.as_ref()],
+        bump
+    )]
+    pub beneficiary_blocklist: UncheckedAccount<'info>,
+
     pub system_program: Program<'info, System>,
     pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>
 }
 
 pub fn process_donation_withdrawal(ctx: Context<DonationWithdrawal>) -> Result<()> {
+    // 设置了共同所有者时需走提案审批
+    require!(!ctx.accounts.crowdfund_account.requires_approval(), ErrorCode::ApprovalRequired);
+
+    msg!("withdraw_token_account key: {}", ctx.accounts.withdraw_token_account.key());
+
+    let amount = withdraw_vested(
+        &ctx.accounts.config,
+        &mut ctx.accounts.crowdfund_account,
+        &ctx.accounts.campaign_token_account,
+        &ctx.accounts.withdraw_token_account,
+        &ctx.accounts.beneficiary_blocklist,
+        &ctx.accounts.mint,
+        &ctx.accounts.token_program
+    )?;
+
     let crowdfund_account = &ctx.accounts.crowdfund_account;
+
+    emit!(WithdrawMade {
+        withdrawer: ctx.accounts.owner.key(),
+        beneficiary: crowdfund_account.beneficiary,
+        amount,
+        withdrawn_amount: crowdfund_account.withdrawn_amount
+    });
+    
+    Ok(())
+}
+
+// 检查提款条件并转出已归属的资金，返回本次提取金额
+pub fn withdraw_vested<'info>(
+    config: &Config,
+    crowdfund_account: &mut Account<'info, Crowdfund>,
+    campaign_token_account: &InterfaceAccount<'info, TokenAccount>,
+    withdraw_token_account: &InterfaceAccount<'info, TokenAccount>,
+    beneficiary_blocklist: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>
+) -> Result<u64> {
+    require!(!config.paused, ErrorCode::ProgramPaused);
+    require_not_blocked(beneficiary_blocklist)?;
+
     let now = Clock::get()?.unix_timestamp;
 
     require!(now >= crowdfund_account.start_time, ErrorCode::NoStared);
 
+    // 截止时间变更后的宽限期内，早期支持者仍可退出，暂不允许提款
+    if let Some(grace_period_end) = crowdfund_account.grace_period_end(config.extension_grace_period) {
+        require!(now > grace_period_end, ErrorCode::GracePeriodActive);
+    }
+
     require!(crowdfund_account.state == CampaignState::Success as u8, ErrorCode::WithdrawalNotAllowed);
-    require!(!crowdfund_account.is_withdrawals, ErrorCode::WithdrawalNotAllowed);
 
-    require!(crowdfund_account.raised_amount >= crowdfund_account.target_amount, ErrorCode::NotReaching);
+    // 成功后的争议期内不允许提款，仲裁放行后除外
+    if !crowdfund_account.is_dispute_resolved {
+        let dispute_window_end = crowdfund_account
+            .dispute_window_end(config.dispute_window)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(now > dispute_window_end, ErrorCode::DisputeWindowActive);
+    }
+
+    // 成功后截止前仍在接受捐款与延伸目标，两种模式都要等到结束后才能提款
+    require!(now > crowdfund_account.end_time, ErrorCode::WithdrawalNotAllowed);
+
+    match crowdfund_account.get_funding_model() {
+        Some(FundingModel::AllOrNothing) => {
+            require!(crowdfund_account.raised_amount >= crowdfund_account.target_amount, ErrorCode::NotReaching);
+        },
+        Some(FundingModel::KeepItAll) => {},
+        None => return Err(ErrorCode::InvalidFundingModel.into()),
+    };
 
-    msg!("withdraw_token_account key: {}", ctx.accounts.withdraw_token_account.key());
-    
-    let withdrawer_key = ctx.accounts.withdrawer.key();
+    require_keys_eq!(campaign_token_account.key(), crowdfund_account.escrow_account, ErrorCode::WithdrawalNotAllowed);
+    require!(
+        withdraw_token_account.owner == crowdfund_account.beneficiary && withdraw_token_account.mint == mint.key(),
+        ErrorCode::InvalidBeneficiary
+    );
+
+    // 推荐奖励留在托管账户中，由推荐人自行领取
+    let payable = crowdfund_account.raised_amount
+        .checked_sub(crowdfund_account.referral_reserve())
+        .ok_or(ErrorCode::Overflow)?;
+
+    // 按归属计划分多次提取，每次只能提取已归属且未提取的部分
+    let amount = crowdfund_account
+        .vested_amount(payable, now)
+        .saturating_sub(crowdfund_account.withdrawn_amount);
+    require!(amount > 0, ErrorCode::NothingVested);
+
+    let creator_key = crowdfund_account.creator;
     let signer_seeds: &[&[&[u8]]] = &[&[
-        withdrawer_key.as_ref(),
-        &[ctx.bumps.crowdfund_account]
+        b"campaign",
+        creator_key.as_ref(),
+        &[crowdfund_account.bump]
     ]];
 
     let cpi_account = TransferChecked {
-        from: ctx.accounts.campaign_token_account.to_account_info(),
-        to: ctx.accounts.withdraw_token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        authority: ctx.accounts.crowdfund_account.to_account_info(),
+        from: campaign_token_account.to_account_info(),
+        to: withdraw_token_account.to_account_info(),
+        mint: mint.to_account_info(),
+        authority: crowdfund_account.to_account_info(),
     };
 
     let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(), 
+        token_program.to_account_info(), 
         cpi_account, 
         signer_seeds
     );
 
-    transfer_checked(cpi_ctx, crowdfund_account.raised_amount, ctx.accounts.mint.decimals)?;
+    transfer_checked(cpi_ctx, amount, mint.decimals)?;
 
-    let crowdfund_account = &mut ctx.accounts.crowdfund_account;
     crowdfund_account.is_withdrawals = true;
+    crowdfund_account.withdrawn_amount = crowdfund_account.withdrawn_amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
 
-    msg!("Withdrawal of target amount {} succeeded.", crowdfund_account.target_amount);
+    msg!("Withdrawal of {} succeeded, {} withdrawn in total.", amount, crowdfund_account.withdrawn_amount);
 
-    emit!(WithdrawMade {
-        withdrawer: withdrawer_key,
-        amount: crowdfund_account.raised_amount
-    });
-    
-    Ok(())
-}
\ No newline at end of file
+    Ok(amount)
+}
@@ -1,14 +1,23 @@
+// anchor 0.31 的 #[program] 在 crate 根生成的 IDL 指令仍调用已弃用的 AccountInfo::realloc，
+// 只对生成代码放行，程序自身的模块照常检查
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
 
+#[warn(deprecated)]
 mod state;
+#[warn(deprecated)]
 mod instructions;
+#[warn(deprecated)]
 mod error;
 
 use instructions::*;
+use state::{CampaignAction, VestingSchedule};
 
 
 declare_id!("H5NDgHeJkob5QMnH5V4BkPBeTjrjwKAvpeTUDvZWWFXP");
 
+#[warn(deprecated)]
 #[program]
 pub mod crowd_fund {
 
@@ -19,15 +28,24 @@ pub mod crowd_fund {
         name: String,
         target_amount: u64,
         start_time: i64,
-        end_time: i64
+        end_time: i64,
+        funding_model: u8,
+        vesting: Option<VestingSchedule>
     ) -> Result<()> {
         msg!("Intialize Campaign");
-        proccess_crowdfund(ctx, name, target_amount, start_time, end_time)
+        proccess_crowdfund(ctx, name, target_amount, start_time, end_time, funding_model, vesting)
     }
 
-    pub fn donation(ctx: Context<InitDonationRecord>, amount: u64) -> Result<()> {
+    pub fn donation(
+        ctx: Context<InitDonationRecord>,
+        amount: u64,
+        tier: Option<u8>,
+        memo: Option<Vec<u8>>,
+        referrer: Option<Pubkey>,
+        allowlist_proof: Option<Vec<[u8; 32]>>
+    ) -> Result<()> {
         msg!("donate {}", amount);
-        proccess_donation_record(ctx, amount)
+        proccess_donation_record(ctx, amount, tier, memo, referrer, allowlist_proof)
     }
 
     pub fn withdraw(ctx: Context<DonationWithdrawal>) -> Result<()> {
@@ -54,5 +72,274 @@ pub mod crowd_fund {
         msg!("reward claim");
         proccess_reward_claim(ctx, proof)
     }
+
+    pub fn holder_reward_claim(ctx: Context<HolderRewardClaim>, amount: u64, proof: Vec<[u8; 32]>) -> Result<()> {
+        msg!("holder reward claim");
+        proccess_holder_reward_claim(ctx, amount, proof)
+    }
+
+    pub fn initialize_config(ctx: Context<InitConfig>) -> Result<()> {
+        msg!("initialize config");
+        proccess_init_config(ctx)
+    }
+
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        msg!("set paused");
+        proccess_set_paused(ctx, paused)
+    }
+
+    pub fn set_campaign_limits(
+        ctx: Context<UpdateConfig>,
+        max_campaign_duration: i64,
+        max_pause_duration: i64
+    ) -> Result<()> {
+        msg!("set campaign limits");
+        proccess_set_campaign_limits(ctx, max_campaign_duration, max_pause_duration)
+    }
+
+    pub fn set_extension_policy(
+        ctx: Context<UpdateConfig>,
+        max_deadline_extensions: u8,
+        extension_grace_period: i64
+    ) -> Result<()> {
+        msg!("set extension policy");
+        proccess_set_extension_policy(ctx, max_deadline_extensions, extension_grace_period)
+    }
+
+    pub fn set_pledge_policy(ctx: Context<UpdateConfig>, lock_pledges_on_success: bool) -> Result<()> {
+        msg!("set pledge policy");
+        proccess_set_pledge_policy(ctx, lock_pledges_on_success)
+    }
+
+    pub fn set_beneficiary_timelock(ctx: Context<UpdateConfig>, beneficiary_timelock: i64) -> Result<()> {
+        msg!("set beneficiary timelock");
+        proccess_set_beneficiary_timelock(ctx, beneficiary_timelock)
+    }
+
+    pub fn set_dispute_policy(
+        ctx: Context<UpdateConfig>,
+        arbiter: Pubkey,
+        dispute_window: i64,
+        dispute_threshold_bps: u16
+    ) -> Result<()> {
+        msg!("set dispute policy");
+        proccess_set_dispute_policy(ctx, arbiter, dispute_window, dispute_threshold_bps)
+    }
+
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        msg!("propose admin");
+        proccess_propose_admin(ctx, new_admin)
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        msg!("accept admin");
+        proccess_accept_admin(ctx)
+    }
+
+    pub fn pause_campaign(ctx: Context<PauseCampaign>) -> Result<()> {
+        msg!("pause campaign");
+        proccess_pause_campaign(ctx)
+    }
+
+    pub fn resume_campaign(ctx: Context<ResumeCampaign>) -> Result<()> {
+        msg!("resume campaign");
+        proccess_resume_campaign(ctx)
+    }
+
+    pub fn update_campaign_metadata(
+        ctx: Context<UpdateCampaignMetadata>,
+        name: String,
+        metadata_uri: String,
+        category: u8,
+        image_hash: [u8; 32]
+    ) -> Result<()> {
+        msg!("update campaign metadata");
+        proccess_update_campaign_metadata(ctx, name, metadata_uri, category, image_hash)
+    }
+
+    pub fn extend_deadline(ctx: Context<ExtendDeadline>, new_end_time: i64) -> Result<()> {
+        msg!("extend deadline to {}", new_end_time);
+        proccess_extend_deadline(ctx, new_end_time)
+    }
+
+    pub fn exit_after_extension(ctx: Context<ExitAfterExtension>) -> Result<()> {
+        msg!("exit after extension");
+        proccess_exit_after_extension(ctx)
+    }
+
+    pub fn migrate_campaign(ctx: Context<MigrateCampaign>) -> Result<()> {
+        msg!("migrate campaign");
+        proccess_migrate_campaign(ctx)
+    }
+
+    pub fn migrate_donation_record(ctx: Context<MigrateDonationRecord>) -> Result<()> {
+        msg!("migrate donation record");
+        proccess_migrate_donation_record(ctx)
+    }
+
+    pub fn unpledge(ctx: Context<Unpledge>, amount: u64) -> Result<()> {
+        msg!("unpledge {}", amount);
+        proccess_unpledge(ctx, amount)
+    }
+
+    pub fn add_reward_tier(ctx: Context<AddRewardTier>, min_pledge: u64, max_quantity: u32) -> Result<()> {
+        msg!("add reward tier");
+        proccess_add_reward_tier(ctx, min_pledge, max_quantity)
+    }
+
+    pub fn mint_backer_badge(ctx: Context<MintBackerBadge>) -> Result<()> {
+        msg!("mint backer badge");
+        proccess_mint_backer_badge(ctx)
+    }
+
+    pub fn enable_pledge_tokens(ctx: Context<EnablePledgeTokens>) -> Result<()> {
+        msg!("enable pledge tokens");
+        proccess_enable_pledge_tokens(ctx)
+    }
+
+    pub fn redeem_pledge_tokens(ctx: Context<RedeemPledgeTokens>, amount: u64) -> Result<()> {
+        msg!("redeem pledge tokens {}", amount);
+        proccess_redeem_pledge_tokens(ctx, amount)
+    }
+
+    pub fn donate_with_intent(
+        ctx: Context<DonateWithIntent>,
+        amount: u64,
+        nonce: u64,
+        expiry: i64
+    ) -> Result<()> {
+        msg!("donate with intent {}", amount);
+        proccess_donate_with_intent(ctx, amount, nonce, expiry)
+    }
+
+    pub fn create_matching_pool(
+        ctx: Context<CreateMatchingPool>,
+        ratio_bps: u16,
+        cap: u64,
+        deposit: u64
+    ) -> Result<()> {
+        msg!("create matching pool");
+        proccess_create_matching_pool(ctx, ratio_bps, cap, deposit)
+    }
+
+    pub fn create_round(
+        ctx: Context<CreateRound>,
+        round_id: u64,
+        start_time: i64,
+        end_time: i64
+    ) -> Result<()> {
+        msg!("create round {}", round_id);
+        proccess_create_round(ctx, round_id, start_time, end_time)
+    }
+
+    pub fn fund_round(ctx: Context<FundRound>, amount: u64) -> Result<()> {
+        msg!("fund round {}", amount);
+        proccess_fund_round(ctx, amount)
+    }
+
+    pub fn add_round_campaign(ctx: Context<AddRoundCampaign>) -> Result<()> {
+        msg!("add round campaign");
+        proccess_add_round_campaign(ctx)
+    }
+
+    pub fn settle_round<'info>(ctx: Context<'_, '_, 'info, 'info, SettleRound<'info>>) -> Result<()> {
+        msg!("settle round");
+        proccess_settle_round(ctx)
+    }
+
+    pub fn sweep_round(ctx: Context<SweepRound>) -> Result<()> {
+        msg!("sweep round");
+        proccess_sweep_round(ctx)
+    }
+
+    pub fn add_stretch_goal(ctx: Context<AddStretchGoal>, target: u64, description_hash: [u8; 32]) -> Result<()> {
+        msg!("add stretch goal");
+        proccess_add_stretch_goal(ctx, target, description_hash)
+    }
+
+    pub fn set_referral_share(ctx: Context<SetReferralShare>, referral_share_bps: u16) -> Result<()> {
+        msg!("set referral share {}", referral_share_bps);
+        proccess_set_referral_share(ctx, referral_share_bps)
+    }
+
+    pub fn claim_referral_reward(ctx: Context<ClaimReferralReward>) -> Result<()> {
+        msg!("claim referral reward");
+        proccess_claim_referral_reward(ctx)
+    }
+
+    pub fn propose_beneficiary(ctx: Context<UpdateBeneficiary>, new_beneficiary: Pubkey) -> Result<()> {
+        msg!("propose beneficiary");
+        proccess_propose_beneficiary(ctx, new_beneficiary)
+    }
+
+    pub fn apply_beneficiary_change(ctx: Context<UpdateBeneficiary>) -> Result<()> {
+        msg!("apply beneficiary change");
+        proccess_apply_beneficiary_change(ctx)
+    }
+
+    pub fn cancel_campaign(ctx: Context<CancelCampaign>) -> Result<()> {
+        msg!("cancel campaign");
+        proccess_cancel_campaign(ctx)
+    }
+
+    pub fn set_co_owners(ctx: Context<SetCoOwners>, co_owners: Vec<Pubkey>, approval_threshold: u8) -> Result<()> {
+        msg!("set co-owners");
+        proccess_set_co_owners(ctx, co_owners, approval_threshold)
+    }
+
+    pub fn propose_action(ctx: Context<ProposeAction>, action: CampaignAction) -> Result<()> {
+        msg!("propose action");
+        proccess_propose_action(ctx, action)
+    }
+
+    pub fn approve_action(ctx: Context<ApproveAction>) -> Result<()> {
+        msg!("approve action");
+        proccess_approve_action(ctx)
+    }
+
+    pub fn execute_action(ctx: Context<ExecuteAction>) -> Result<()> {
+        msg!("execute action");
+        proccess_execute_action(ctx)
+    }
+
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        msg!("propose owner");
+        proccess_propose_owner(ctx, new_owner)
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        msg!("accept owner");
+        proccess_accept_owner(ctx)
+    }
+
+    pub fn set_allowlist(
+        ctx: Context<SetAllowlist>,
+        allowlist_root: Option<[u8; 32]>,
+        allowlist_only: bool,
+        early_access_start: i64
+    ) -> Result<()> {
+        msg!("set allowlist");
+        proccess_set_allowlist(ctx, allowlist_root, allowlist_only, early_access_start)
+    }
+
+    pub fn block_wallet(ctx: Context<BlockWallet>, wallet: Pubkey) -> Result<()> {
+        msg!("block wallet {}", wallet);
+        proccess_block_wallet(ctx, wallet)
+    }
+
+    pub fn unblock_wallet(ctx: Context<UnblockWallet>) -> Result<()> {
+        msg!("unblock wallet");
+        proccess_unblock_wallet(ctx)
+    }
+
+    pub fn flag_campaign(ctx: Context<FlagCampaign>) -> Result<()> {
+        msg!("flag campaign");
+        proccess_flag_campaign(ctx)
+    }
+
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, release: bool) -> Result<()> {
+        msg!("resolve dispute, release: {}", release);
+        proccess_resolve_dispute(ctx, release)
+    }
 }
 
@@ -1,5 +1,35 @@
 use anchor_lang::prelude::*;
 
+use crate::error::ErrorCode;
+
+// Maximum campaign name length reserved when the account is created
+pub const MAX_NAME_LEN: usize = 50;
+// Names set through metadata updates may grow the account up to this length
+pub const MAX_METADATA_NAME_LEN: usize = 100;
+pub const MAX_METADATA_URI_LEN: usize = 200;
+// Default upper bound on end_time - start_time, in seconds (90 days)
+pub const DEFAULT_MAX_CAMPAIGN_DURATION: i64 = 90 * 24 * 60 * 60;
+// Longest pause credited back to the campaign, after it anyone may resume or finalize (7 days)
+pub const DEFAULT_MAX_PAUSE_DURATION: i64 = 7 * 24 * 60 * 60;
+pub const DEFAULT_MAX_DEADLINE_EXTENSIONS: u8 = 3;
+// Window after a deadline change during which earlier backers may exit (3 days)
+pub const DEFAULT_EXTENSION_GRACE_PERIOD: i64 = 3 * 24 * 60 * 60;
+// Delay before a proposed withdrawal beneficiary takes effect (2 days)
+pub const DEFAULT_BENEFICIARY_TIMELOCK: i64 = 2 * 24 * 60 * 60;
+// Backers may flag a campaign for this long after it succeeds (3 days)
+pub const DEFAULT_DISPUTE_WINDOW: i64 = 3 * 24 * 60 * 60;
+pub const DEFAULT_DISPUTE_THRESHOLD_BPS: u16 = 1_000;
+pub const MAX_REWARD_TIERS: usize = 10;
+pub const MAX_STRETCH_GOALS: usize = 5;
+pub const MAX_CO_OWNERS: usize = 5;
+// Co-owners plus the owner
+pub const MAX_APPROVALS: usize = MAX_CO_OWNERS + 1;
+// Proposals not executed within this time can no longer be executed (7 days)
+pub const PROPOSAL_TTL: i64 = 7 * 24 * 60 * 60;
+pub const MAX_MEMO_LEN: usize = 128;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_ROUND_CAMPAIGNS: usize = 10;
+
 // Define an enum for campaign state
 #[repr(u8)]
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
@@ -7,6 +37,7 @@ pub enum CampaignState {
     Active = 0,   // in progress
     Success = 1,  // success
     Fail = 2,     // fail
+    Disputed = 3, // flagged by backers, waiting for the arbiter
 }
 
 impl CampaignState {
@@ -15,11 +46,50 @@ impl CampaignState {
             0 => Some(CampaignState::Active),
             1 => Some(CampaignState::Success),
             2 => Some(CampaignState::Fail),
+            3 => Some(CampaignState::Disputed),
+            _ => None,
+        }
+    }
+}
+
+// How raised funds are released once the campaign ends
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub enum FundingModel {
+    AllOrNothing = 0,  // payout only if the target is reached, otherwise refunds
+    KeepItAll = 1,     // creator keeps whatever was raised, no refunds
+}
+
+impl FundingModel {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(FundingModel::AllOrNothing),
+            1 => Some(FundingModel::KeepItAll),
             _ => None,
         }
     }
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RewardTier {
+    pub min_pledge: u64,
+    pub max_quantity: u32,
+    pub claimed: u32,
+}
+
+// Optional withdrawal schedule passed at campaign creation, in seconds after success
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy)]
+pub struct VestingSchedule {
+    pub cliff: i64,
+    pub duration: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct StretchGoal {
+    pub target: u64,
+    pub description_hash: [u8; 32],
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct Crowdfund {
@@ -33,13 +103,210 @@ pub struct Crowdfund {
     pub raised_amount: u64,
     pub state: u8,
     pub is_withdrawals: bool,
-    pub merkle_root: [u8; 32]
+    pub merkle_root: [u8; 32],
+    pub is_paused: bool,
+    pub paused_at: i64,
+    #[max_len(200)]
+    pub metadata_uri: String,
+    pub category: u8,
+    pub image_hash: [u8; 32],
+    pub extension_count: u8,
+    pub last_extended_at: i64,
+    pub bump: u8,
+    pub funding_model: u8,
+    #[max_len(10)]
+    pub reward_tiers: Vec<RewardTier>,
+    pub pledge_mint: Option<Pubkey>,
+    pub matched_amount: u64,
+    #[max_len(5)]
+    pub stretch_goals: Vec<StretchGoal>,
+    // Number of stretch goals crossed so far, goals are ordered by target
+    pub stretch_goals_reached: u8,
+    pub referral_share_bps: u16,
+    // Donations that came in through a referrer
+    pub referred_amount: u64,
+    // Linear vesting of withdrawals from `succeeded_at`, zero duration releases everything at once
+    pub vesting_cliff: i64,
+    pub vesting_duration: i64,
+    pub succeeded_at: i64,
+    pub withdrawn_amount: u64,
+    // Receives withdrawals, changes only take effect after the config timelock
+    pub beneficiary: Pubkey,
+    pub pending_beneficiary: Option<Pubkey>,
+    pub beneficiary_effective_at: i64,
+    // With a non-zero threshold, sensitive actions need that many approvals from owner and co-owners
+    #[max_len(MAX_CO_OWNERS)]
+    pub co_owners: Vec<Pubkey>,
+    pub approval_threshold: u8,
+    pub proposal_count: u64,
+    // Cancelled campaigns are failed and refundable regardless of funding model
+    pub is_cancelled: bool,
+    // Key the campaign PDA is derived from, stays fixed when ownership moves
+    pub creator: Pubkey,
+    pub pending_owner: Option<Pubkey>,
+    // Merkle root over hashed funding wallet pubkeys, allowlisted wallets may start at early_access_start
+    pub allowlist_root: Option<[u8; 32]>,
+    pub allowlist_only: bool,
+    pub early_access_start: i64,
+    // Pledges of backers who flagged the campaign during the dispute window
+    pub flagged_amount: u64,
+    pub is_dispute_resolved: bool,
+    // Sponsor matching pool, once created every donation must go through it
+    pub matching_pool: Option<Pubkey>,
 }
 
 impl Crowdfund {
     pub fn get_state(&self) -> Option<CampaignState> {
         CampaignState::from_u8(self.state)
     }
+
+    pub fn get_funding_model(&self) -> Option<FundingModel> {
+        FundingModel::from_u8(self.funding_model)
+    }
+
+    // Withdrawals stay locked until backers had the chance to dispute the success
+    pub fn dispute_window_end(&self, dispute_window: i64) -> Option<i64> {
+        self.succeeded_at.checked_add(dispute_window)
+    }
+
+    // Success that donations, exits, cancellation and disputes can no longer change
+    pub fn is_final_success(&self, config: &Config, now: i64) -> bool {
+        if self.get_state() != Some(CampaignState::Success) || now <= self.end_time {
+            return false;
+        }
+        // Exits, cancellation and flags are closed once the owner has withdrawn
+        if self.is_withdrawals {
+            return true;
+        }
+
+        let grace_over = self
+            .grace_period_end(config.extension_grace_period)
+            .is_none_or(|grace_period_end| now > grace_period_end);
+        let dispute_over = self.is_dispute_resolved
+            || self
+                .dispute_window_end(config.dispute_window)
+                .is_some_and(|dispute_window_end| now > dispute_window_end);
+
+        grace_over && dispute_over
+    }
+
+    // Outcome is settled, funds can only leave through withdrawals, refunds and claims
+    pub fn is_final(&self, config: &Config, now: i64) -> bool {
+        self.get_state() == Some(CampaignState::Fail) || self.is_final_success(config, now)
+    }
+
+    // Earliest time a donor may give, allowlisted donors get the early access window
+    pub fn opens_at(&self, allowlisted: bool) -> i64 {
+        if allowlisted && self.early_access_start > 0 {
+            self.early_access_start
+        } else {
+            self.start_time
+        }
+    }
+
+    pub fn is_member(&self, key: &Pubkey) -> bool {
+        self.owner == *key || self.co_owners.contains(key)
+    }
+
+    pub fn requires_approval(&self) -> bool {
+        self.approval_threshold > 0
+    }
+
+    // Time after which a pause no longer blocks resume or finalize for anyone
+    pub fn pause_expires_at(&self, max_pause_duration: i64) -> Option<i64> {
+        self.paused_at.checked_add(max_pause_duration)
+    }
+
+    // Pause time added back to end_time on resume, capped by the pause limit and
+    // by what still fits under the maximum campaign duration
+    pub fn pause_credit(&self, now: i64, max_pause_duration: i64, max_campaign_duration: i64) -> i64 {
+        let paused_duration = now
+            .saturating_sub(self.paused_at)
+            .clamp(0, max_pause_duration.max(0));
+        let headroom = max_campaign_duration
+            .saturating_sub(self.end_time.saturating_sub(self.start_time))
+            .max(0);
+        paused_duration.min(headroom)
+    }
+
+    // End of the backer exit window opened by the latest deadline change
+    pub fn grace_period_end(&self, grace_period: i64) -> Option<i64> {
+        if self.extension_count == 0 {
+            return None;
+        }
+        self.last_extended_at.checked_add(grace_period)
+    }
+
+    // Remove a returned pledge and the sponsor match it earned from the raised total,
+    // reopening the campaign if it drops below target
+    pub fn release_pledge(&mut self, amount: u64, matched: u64) -> Option<()> {
+        self.matched_amount = self.matched_amount.checked_sub(matched)?;
+        self.raised_amount = self.raised_amount.checked_sub(amount.checked_add(matched)?)?;
+        if self.get_state() == Some(CampaignState::Success) && self.raised_amount < self.target_amount {
+            self.state = CampaignState::Active as u8;
+            // Flags were raised against the success that just ended
+            self.flagged_amount = 0;
+        }
+        Some(())
+    }
+
+    // Stop counting the flagged part of `amount` leaving a backer's pledge, call before release_pledge
+    pub fn release_flag(&mut self, record: &mut DonationRecord, amount: u64) {
+        let released = record.current_flag(self.succeeded_at).min(amount);
+        record.flagged_amount -= released;
+        self.flagged_amount = self.flagged_amount.saturating_sub(released);
+    }
+
+    // Reserve one unit of a reward tier for a pledge of `amount`
+    pub fn claim_tier(&mut self, index: u8, amount: u64) -> Result<()> {
+        let tier = self.reward_tiers.get_mut(index as usize).ok_or(ErrorCode::InvalidRewardTier)?;
+        require!(amount >= tier.min_pledge, ErrorCode::PledgeBelowTierMinimum);
+        require!(tier.claimed < tier.max_quantity, ErrorCode::RewardTierSoldOut);
+
+        tier.claimed += 1;
+        Ok(())
+    }
+
+    // Give a reward tier unit back when its pledge is refunded or withdrawn
+    pub fn release_tier(&mut self, index: u8) {
+        if let Some(tier) = self.reward_tiers.get_mut(index as usize) {
+            tier.claimed = tier.claimed.saturating_sub(1);
+        }
+    }
+
+    pub fn mark_success(&mut self, now: i64) {
+        if self.state != CampaignState::Success as u8 {
+            self.state = CampaignState::Success as u8;
+            self.succeeded_at = now;
+        }
+    }
+
+    // Portion of `total` the owner may have withdrawn by `now`
+    pub fn vested_amount(&self, total: u64, now: i64) -> u64 {
+        if self.vesting_duration <= 0 {
+            return total;
+        }
+
+        let elapsed = now.saturating_sub(self.succeeded_at);
+        if elapsed < self.vesting_cliff {
+            return 0;
+        }
+        if elapsed >= self.vesting_duration {
+            return total;
+        }
+
+        (total as u128 * elapsed as u128 / self.vesting_duration as u128) as u64
+    }
+
+    // Part of the escrow held back at withdrawal for referrer rewards
+    pub fn referral_reserve(&self) -> u64 {
+        referral_share(self.referred_amount, self.referral_share_bps)
+    }
+
+    // Account size needed to hold a name of `name_len` bytes
+    pub fn space_with_name(name_len: usize) -> usize {
+        8 + Crowdfund::INIT_SPACE + name_len.saturating_sub(MAX_NAME_LEN)
+    }
 }
 
 
@@ -51,4 +318,471 @@ pub struct DonationRecord {
     pub donor: Pubkey,
     pub amount: u64,
     pub is_refunded: bool,
+    pub donated_at: i64,
+    pub bump: u8,
+    pub tier: Option<u8>,
+    pub has_badge: bool,
+    pub matched_amount: u64,
+    pub round: Option<Pubkey>,
+    pub round_contribution: u64,
+    pub round_weight: u64,
+    pub referrer: Option<Pubkey>,
+    // Set when a refund was withheld because the donor is blocked
+    pub is_frozen: bool,
+    // Part of the pledge counted in the campaign's flagged_amount, for the success at `flagged_at`
+    pub flagged_amount: u64,
+    pub flagged_at: i64,
+}
+
+impl DonationRecord {
+    // Sponsor match attributable to `amount` of this pledge
+    pub fn matched_share(&self, amount: u64) -> u64 {
+        if self.amount == 0 {
+            return 0;
+        }
+        (self.matched_amount as u128 * amount as u128 / self.amount as u128) as u64
+    }
+
+    // Flag still counted against the campaign's current success, zero once it reopened
+    pub fn current_flag(&self, succeeded_at: i64) -> u64 {
+        if self.flagged_at == succeeded_at {
+            self.flagged_amount
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RoundCampaign {
+    pub campaign: Pubkey,
+    pub escrow_account: Pubkey,
+    // Sum of sqrt(contribution) over donors, and the plain sum of contributions
+    pub sum_sqrt: u64,
+    pub sum_contributions: u64,
+    pub match_amount: u64,
+}
+
+// Quadratic funding round: a matching pot shared between participating campaigns
+#[account]
+#[derive(InitSpace)]
+pub struct Round {
+    pub admin: Pubkey,
+    pub round_id: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    #[max_len(10)]
+    pub campaigns: Vec<RoundCampaign>,
+    pub is_settled: bool,
+    pub bump: u8,
+}
+
+impl Round {
+    pub fn is_open(&self, now: i64) -> bool {
+        !self.is_settled && now >= self.start_time && now <= self.end_time
+    }
+
+    // Bring a campaign's totals in line with the donor's current pledge
+    pub fn sync_contribution(&mut self, campaign: &Pubkey, record: &mut DonationRecord) -> Result<()> {
+        if self.is_settled {
+            return Ok(());
+        }
+
+        let entry = self.campaigns
+            .iter_mut()
+            .find(|entry| entry.campaign == *campaign)
+            .ok_or(ErrorCode::InvalidRound)?;
+
+        let contribution = if record.is_refunded { 0 } else { record.amount };
+        let weight = isqrt(contribution);
+
+        entry.sum_sqrt = entry.sum_sqrt
+            .checked_sub(record.round_weight)
+            .and_then(|sum| sum.checked_add(weight))
+            .ok_or(ErrorCode::Overflow)?;
+        entry.sum_contributions = entry.sum_contributions
+            .checked_sub(record.round_contribution)
+            .and_then(|sum| sum.checked_add(contribution))
+            .ok_or(ErrorCode::Overflow)?;
+
+        record.round_weight = weight;
+        record.round_contribution = contribution;
+        Ok(())
+    }
+}
+
+// Quadratic funding weight of a campaign: (sum of sqrt contributions)^2 - sum of contributions
+pub fn quadratic_weight(sum_sqrt: u64, sum_contributions: u64) -> Option<u128> {
+    (sum_sqrt as u128)
+        .checked_mul(sum_sqrt as u128)
+        .map(|square| square.saturating_sub(sum_contributions as u128))
+}
+
+// Part of the round pot paid to a campaign with `weight` out of `total_weight`
+pub fn round_share(pot: u64, weight: u128, total_weight: u128) -> Option<u64> {
+    if total_weight == 0 {
+        return Some(0);
+    }
+    (pot as u128)
+        .checked_mul(weight)
+        .map(|value| value / total_weight)
+        .and_then(|value| u64::try_from(value).ok())
+}
+
+// Integer square root, rounded down
+pub fn isqrt(value: u64) -> u64 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2 + x % 2;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+// Sponsor funds that match donations to a campaign, held in the pool's token account
+#[account]
+#[derive(InitSpace)]
+pub struct MatchingPool {
+    pub campaign: Pubkey,
+    pub sponsor: Pubkey,
+    pub vault: Pubkey,
+    pub ratio_bps: u16,
+    pub cap: u64,
+    pub matched_amount: u64,
+    pub is_settled: bool,
+    pub bump: u8,
+}
+
+impl MatchingPool {
+    // Match owed for a donation, limited by the remaining cap and the vault balance
+    pub fn match_for(&self, amount: u64, vault_balance: u64) -> u64 {
+        let matched = (amount as u128 * self.ratio_bps as u128 / BPS_DENOMINATOR as u128) as u64;
+        matched
+            .min(self.cap.saturating_sub(self.matched_amount))
+            .min(vault_balance)
+    }
+}
+
+
+// Sensitive campaign actions that need co-owner approval
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub enum CampaignAction {
+    Withdraw,
+    SetMerkleRoot {
+        merkle_root: [u8; 32],
+    },
+    Cancel,
+    UpdateMetadata {
+        #[max_len(MAX_METADATA_NAME_LEN)]
+        name: String,
+        #[max_len(MAX_METADATA_URI_LEN)]
+        metadata_uri: String,
+        category: u8,
+        image_hash: [u8; 32],
+    },
+    ProposeBeneficiary {
+        new_beneficiary: Pubkey,
+    },
+    // Rotate co-owners, an empty list with a zero threshold returns control to the owner alone
+    SetCoOwners {
+        #[max_len(MAX_CO_OWNERS)]
+        co_owners: Vec<Pubkey>,
+        approval_threshold: u8,
+    },
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Proposal {
+    pub campaign: Pubkey,
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub action: CampaignAction,
+    #[max_len(MAX_APPROVALS)]
+    pub approvals: Vec<Pubkey>,
+    pub is_executed: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Proposal {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.created_at.checked_add(PROPOSAL_TTL).is_none_or(|expires_at| now > expires_at)
+    }
+
+    // Campaign account size after executing this proposal
+    pub fn campaign_space(&self, crowdfund: &Crowdfund) -> usize {
+        match &self.action {
+            CampaignAction::UpdateMetadata { name, .. } => Crowdfund::space_with_name(name.len()),
+            _ => Crowdfund::space_with_name(crowdfund.name.len()),
+        }
+    }
+}
+
+// Volume a referrer brought to one campaign
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerStats {
+    pub campaign: Pubkey,
+    pub referrer: Pubkey,
+    pub referred_amount: u64,
+    pub donation_count: u32,
+    pub is_claimed: bool,
+    pub bump: u8,
+}
+
+pub fn referral_share(amount: u64, share_bps: u16) -> u64 {
+    (amount as u128 * share_bps as u128 / BPS_DENOMINATOR as u128) as u64
+}
+
+// Wallet blocked platform-wide by the program admin
+#[account]
+#[derive(InitSpace)]
+pub struct BlockedWallet {
+    pub wallet: Pubkey,
+    pub blocked_at: i64,
+    pub bump: u8,
+}
+
+// Pledge tokens a blocked holder burned for a refund, paid out once the holder is unblocked
+#[account]
+#[derive(InitSpace)]
+pub struct FrozenRedemption {
+    pub campaign: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+// Marks a pledge token holder's reward as claimed
+#[account]
+#[derive(InitSpace)]
+pub struct HolderClaim {
+    pub campaign: Pubkey,
+    pub holder: Pubkey,
+    pub amount: u64,
+    pub claimed_at: i64,
+    pub bump: u8,
+}
+
+// Marks a donation intent nonce as used so a signed intent cannot be replayed
+#[account]
+#[derive(InitSpace)]
+pub struct IntentNonce {
+    pub donor: Pubkey,
+    pub nonce: u64,
+    pub used_at: i64,
+}
+
+
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub pending_admin: Option<Pubkey>,
+    pub paused: bool,
+    pub bump: u8,
+    pub max_campaign_duration: i64,
+    pub max_deadline_extensions: u8,
+    pub extension_grace_period: i64,
+    pub lock_pledges_on_success: bool,
+    pub beneficiary_timelock: i64,
+    // Decides disputed campaigns
+    pub arbiter: Pubkey,
+    pub dispute_window: i64,
+    pub dispute_threshold_bps: u16,
+    pub max_pause_duration: i64,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUCCEEDED_AT: i64 = 1_700_000_000;
+
+    // 全零数据反序列化为空众筹，只设置测试需要的字段
+    fn vesting_campaign(cliff: i64, duration: i64) -> Crowdfund {
+        let data = vec![0u8; Crowdfund::INIT_SPACE];
+        let mut crowdfund = Crowdfund::deserialize(&mut data.as_slice()).unwrap();
+        crowdfund.vesting_cliff = cliff;
+        crowdfund.vesting_duration = duration;
+        crowdfund.succeeded_at = SUCCEEDED_AT;
+        crowdfund
+    }
+
+    #[test]
+    fn vested_amount_without_schedule_releases_everything() {
+        let crowdfund = vesting_campaign(0, 0);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT), 1_000);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT - 1), 1_000);
+    }
+
+    #[test]
+    fn vested_amount_before_and_at_cliff() {
+        let crowdfund = vesting_campaign(100, 1_000);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT), 0);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 99), 0);
+        // 到达悬崖期时一次性释放已线性累计的部分
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 100), 100);
+    }
+
+    #[test]
+    fn vested_amount_mid_vesting_rounds_down() {
+        let crowdfund = vesting_campaign(0, 3);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 1), 333);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 2), 666);
+
+        let crowdfund = vesting_campaign(100, 1_000);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 500), 500);
+        assert_eq!(crowdfund.vested_amount(u64::MAX, SUCCEEDED_AT + 500), u64::MAX / 2);
+    }
+
+    #[test]
+    fn vested_amount_after_full_duration() {
+        let crowdfund = vesting_campaign(100, 1_000);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 1_000), 1_000);
+        assert_eq!(crowdfund.vested_amount(1_000, i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn vesting_restarts_when_success_is_lost_and_regained() {
+        let mut crowdfund = vesting_campaign(0, 1_000);
+        crowdfund.target_amount = 1_000;
+        crowdfund.raised_amount = 1_000;
+        crowdfund.mark_success(SUCCEEDED_AT);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 500), 500);
+
+        // 撤回后低于目标，恢复为进行中
+        crowdfund.release_pledge(100, 0).unwrap();
+        assert!(crowdfund.get_state() == Some(CampaignState::Active));
+
+        // 再次成功时从新的成功时间重新开始归属
+        crowdfund.raised_amount = 1_000;
+        crowdfund.mark_success(SUCCEEDED_AT + 600);
+        assert_eq!(crowdfund.succeeded_at, SUCCEEDED_AT + 600);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 600), 0);
+        assert_eq!(crowdfund.vested_amount(1_000, SUCCEEDED_AT + 1_100), 500);
+
+        // 已成功时重复标记不会重置归属起点
+        crowdfund.mark_success(SUCCEEDED_AT + 900);
+        assert_eq!(crowdfund.succeeded_at, SUCCEEDED_AT + 600);
+    }
+
+    #[test]
+    fn resume_credits_only_the_pause_time_that_fits_under_the_max_duration() {
+        const MAX_PAUSE: i64 = 7 * 24 * 60 * 60;
+        const MAX_DURATION: i64 = 90 * 24 * 60 * 60;
+
+        let mut crowdfund = vesting_campaign(0, 0);
+        crowdfund.start_time = SUCCEEDED_AT;
+        crowdfund.paused_at = SUCCEEDED_AT + 100;
+
+        // 创建时已达到最长时长：恢复不顺延，但不会失败
+        crowdfund.end_time = SUCCEEDED_AT + MAX_DURATION;
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + MAX_PAUSE, MAX_PAUSE, MAX_DURATION), 0);
+
+        // 只剩一小时额度时最多顺延一小时
+        crowdfund.end_time = SUCCEEDED_AT + MAX_DURATION - 3_600;
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + 60, MAX_PAUSE, MAX_DURATION), 60);
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + MAX_PAUSE, MAX_PAUSE, MAX_DURATION), 3_600);
+
+        // 额度充足时按暂停时长顺延，超过暂停上限的部分不计
+        crowdfund.end_time = SUCCEEDED_AT + 3_600;
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + 500, MAX_PAUSE, MAX_DURATION), 500);
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + 2 * MAX_PAUSE, MAX_PAUSE, MAX_DURATION), MAX_PAUSE);
+
+        // 上限被调低后已超出上限的众筹同样可以恢复
+        assert_eq!(crowdfund.pause_credit(SUCCEEDED_AT + 100 + 500, MAX_PAUSE, 1_000), 0);
+    }
+
+    #[test]
+    fn flags_leave_with_the_pledge_and_reset_when_success_is_lost() {
+        let mut crowdfund = vesting_campaign(0, 0);
+        crowdfund.target_amount = 1_000;
+        crowdfund.raised_amount = 1_500;
+        crowdfund.mark_success(SUCCEEDED_AT);
+
+        let data = vec![0u8; DonationRecord::INIT_SPACE];
+        let mut record = DonationRecord::deserialize(&mut data.as_slice()).unwrap();
+        record.amount = 400;
+        record.flagged_amount = 300;
+        record.flagged_at = crowdfund.succeeded_at;
+        crowdfund.flagged_amount = 500;
+
+        // 撤回的金额优先从已标记部分扣除
+        crowdfund.release_flag(&mut record, 200);
+        crowdfund.release_pledge(200, 0).unwrap();
+        assert_eq!(record.flagged_amount, 100);
+        assert_eq!(crowdfund.flagged_amount, 300);
+
+        // 低于目标恢复为进行中，之前的标记全部作废
+        crowdfund.release_flag(&mut record, 400);
+        crowdfund.release_pledge(400, 0).unwrap();
+        assert!(crowdfund.get_state() == Some(CampaignState::Active));
+        assert_eq!(crowdfund.flagged_amount, 0);
+
+        record.flagged_amount = 200;
+        crowdfund.raised_amount = 1_000;
+        crowdfund.mark_success(SUCCEEDED_AT + 600);
+        assert_eq!(record.current_flag(crowdfund.succeeded_at), 0);
+    }
+
+    #[test]
+    fn isqrt_small_values() {
+        let expected = [0, 1, 1, 1, 2, 2, 2, 2, 2, 3, 3];
+        for (value, root) in expected.iter().enumerate() {
+            assert_eq!(isqrt(value as u64), *root);
+        }
+    }
+
+    #[test]
+    fn isqrt_rounds_down_around_squares() {
+        for root in [10u64, 1_000, 65_535, 3_037_000_499] {
+            assert_eq!(isqrt(root * root), root);
+            assert_eq!(isqrt(root * root - 1), root - 1);
+            assert_eq!(isqrt(root * root + 1), root);
+        }
+    }
+
+    #[test]
+    fn isqrt_max_value() {
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64);
+    }
+
+    #[test]
+    fn quadratic_weight_rewards_many_small_donors() {
+        // 一个捐赠者捐 100：(√100)² - 100 = 0
+        assert_eq!(quadratic_weight(10, 100), Some(0));
+        // 四个捐赠者各捐 25：(4 × 5)² - 100 = 300
+        assert_eq!(quadratic_weight(20, 100), Some(300));
+        // 向下取整的平方根可能让平方小于总额，结果不为负
+        assert_eq!(quadratic_weight(1, 3), Some(0));
+        assert_eq!(quadratic_weight(u64::MAX, 0), Some(u64::MAX as u128 * u64::MAX as u128));
+    }
+
+    #[test]
+    fn round_share_is_pro_rata_and_rounds_down() {
+        assert_eq!(round_share(1_000, 300, 400), Some(750));
+        assert_eq!(round_share(1_000, 100, 400), Some(250));
+        assert_eq!(round_share(1_000, 1, 3), Some(333));
+        assert_eq!(round_share(1_000, 0, 400), Some(0));
+        assert_eq!(round_share(1_000, 5, 0), Some(0));
+        assert_eq!(round_share(u64::MAX, 1, 1), Some(u64::MAX));
+    }
+
+    #[test]
+    fn round_shares_never_exceed_pot() {
+        let weights = [7u128, 11, 13];
+        let total: u128 = weights.iter().sum();
+        let paid: u64 = weights.iter().map(|weight| round_share(1_000, *weight, total).unwrap()).sum();
+        assert!(paid <= 1_000);
+        assert!(1_000 - paid < weights.len() as u64);
+    }
 }
@@ -2,23 +2,27 @@ import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { CrowdFund } from "../target/types/crowd_fund";
 import {
+  ComputeBudgetProgram,
   Keypair,
   LAMPORTS_PER_SOL,
   PublicKey,
   SystemProgram,
+  SYSVAR_CLOCK_PUBKEY,
   Transaction
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   createMint,
+  getAccount,
+  getAssociatedTokenAddressSync,
   mintTo,
   getOrCreateAssociatedTokenAccount,
+  transfer,
 } from "@solana/spl-token";
-import * as fs from "fs";
-import { Client as PgClient } from "pg";
 import { MerkleTree } from "merkletreejs";
 import crypto from "crypto";
-import { on } from "events";
+import { expect } from "chai";
 
 describe("crowd-fund", () => {
   const provider = anchor.AnchorProvider.env();
@@ -33,6 +37,16 @@ describe("crowd-fund", () => {
   // SPL 代币 mint 地址
   let mint: PublicKey;
 
+  // 全局配置 PDA，所有指令都会读取
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("config")],
+    program.programId
+  );
+  const [programDataPda] = PublicKey.findProgramAddressSync(
+    [program.programId.toBuffer()],
+    new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+  );
+
   // 捐赠者账户数组
   const donors: { keypair: Keypair; tokenAccount: PublicKey }[] = [];
 
@@ -40,6 +54,58 @@ describe("crowd-fund", () => {
   const NUM_DONORS = 6;
   // 每个捐赠者需要空投的 SOL 数量
   const AIRDROP_AMOUNT = 2 * LAMPORTS_PER_SOL;
+  // 每个捐赠者铸造的代币数量 (1000 枚，小数位为 2)
+  const MINT_AMOUNT = 1000 * 100;
+
+  // 众筹创建后多久开始、持续多久（秒），开始时间不能早于链上时间
+  const START_DELAY = 3;
+  const CAMPAIGN_DURATION = 8;
+
+  // 筹款模式：全有或全无 / 灵活众筹
+  const ALL_OR_NOTHING = 0;
+  const KEEP_IT_ALL = 1;
+
+  // 测试中缩短的配置时间窗口（秒）
+  const MAX_CAMPAIGN_DURATION = 90 * 24 * 60 * 60;
+  const MAX_PAUSE_DURATION = 4;
+  const EXTENSION_GRACE_PERIOD = 10;
+  const BENEFICIARY_TIMELOCK = 4;
+  const DISPUTE_WINDOW = 2;
+  const DISPUTE_THRESHOLD_BPS = 1000;
+
+  interface TestCampaign {
+    maker: Keypair;
+    crowdfund: PublicKey;
+    escrow: PublicKey;
+    beneficiary: PublicKey;
+    startTime: number;
+    endTime: number;
+  }
+
+  interface CampaignOptions {
+    name?: string;
+    target?: number;
+    startTime?: number;
+    duration?: number;
+    fundingModel?: number;
+    // 收款人，默认为发起人
+    beneficiary?: PublicKey;
+    // 成功后按秒线性归属，均从成功时间起算
+    vesting?: { cliff: number; duration: number };
+  }
+
+  interface DonateOptions {
+    tier?: number;
+    memo?: Buffer;
+    reference?: PublicKey;
+    // 支付捐赠记录租金的钱包，默认为出资人
+    payer?: Keypair;
+    // 捐赠记录归属人，默认为出资人
+    beneficiary?: PublicKey;
+    referrer?: PublicKey;
+    allowlistProof?: number[][];
+    accounts?: object;
+  }
 
   // 模拟 SOL 空投 (本地环境没有requestAirdrop的替代方案)
   async function fundAccount(from: Keypair, to: PublicKey, amount: number) {
@@ -59,32 +125,6 @@ describe("crowd-fund", () => {
     return crypto.createHash("sha256").update(data).digest();
   };
 
-  interface DonorInfo {
-    keypair: Keypair;
-    publicKey: PublicKey;
-    donationAmount: number;
-    tokenAccount: PublicKey;
-  }
-
-
-  // 从 donors.json 中加载捐赠者信息（假设文件中保存了 secretKey 和 publicKey 字符串）
-  function loadDonors(): DonorInfo[] {
-    // 读取 donors.json 文件（请根据实际路径调整）
-    const donorsData = JSON.parse(fs.readFileSync("donors.json", "utf8"));
-
-    // donorsData 中每个对象包含 secretKey, publicKey, tokenAccount（存储为字符串）
-    return donorsData.map((donor: any, index: number) => {
-      // 根据索引分配捐赠金额：偶数索引 4500，奇数索引 3000
-      const donationAmount = (index % 2 === 0) ? 4500 : 3000;
-      return {
-        keypair: Keypair.fromSecretKey(new Uint8Array(donor.secretKey)),
-        publicKey: new PublicKey(donor.publicKey),
-        donationAmount,
-        tokenAccount: new PublicKey(donor.tokenAccount)
-      };
-    });
-  };
-
   function getMerkleProofForLeaf(tree: MerkleTree, leaf: Buffer): number[][] {
     // tree.getProof 返回一个数组，其中每个元素形如 { position: 'left' | 'right', data: Buffer }
     const proofBuffers: Buffer[] = tree.getProof(leaf).map(x => x.data);
@@ -126,281 +166,1983 @@ function verifyMerkleProof(leaf: Buffer, proof: number[][], root: Buffer): Buffe
     return computedHash;
   }
 
-  // 从 PostgreSQL 查询最新的 Merkle Tree 数据（假设表名为 merkle_trees，字段 tree_data 为 JSONB）
-  async function getMerkleTreeData(): Promise<{ merkleRoot: string; leaves: Buffer[] }> {
-    const pgClient = new PgClient({
-      host: "localhost",
-      database: "postgres",
-      user: "postgres",
-      password: "123456",
-    });
-    await pgClient.connect();
-    const res = await pgClient.query("SELECT merkle_root, tree_data FROM merkle_trees ORDER BY id DESC LIMIT 1");
-    await pgClient.end();
-    if (res.rows.length === 0) {
-      throw new Error("No Merkle Tree record found in PostgreSQL");
-    }
-    const merkleRootHex: string = res.rows[0].merkle_root;
-    const treeData: string[][] = res.rows[0].tree_data; // 假设 tree_data 是二维数组，第一层为 leaves（十六进制字符串）
-    // 将第一层叶子转换为 Buffer
-    const leaves = treeData[0].map(hexStr => Buffer.from(hexStr, "hex"));
-    
-    return { merkleRoot: merkleRootHex, leaves };
-  }
-  // before(async () => {
-  //   // 1. 创建一个新的 SPL 代币 mint (小数位为9，类似SOL)
-  //   mint = await createMint(
-  //     connection,
-  //     payer,
-  //     payer.publicKey,
-  //     null,
-  //     2,
-  //   );
-  //   console.log("Mint created:", mint.toBase58());
-
-  //   // 2. 创建捐赠者账户，转入SOL，创建关联token账户并铸造代币
-  //   for (let i = 0; i < NUM_DONORS; i++) {
-  //     const donorKeypair = Keypair.generate();
-
-  //     // 模拟 SOL 空投（直接转账）
-  //     await fundAccount(payer, donorKeypair.publicKey, AIRDROP_AMOUNT);
-  //     console.log(`Donor ${i + 1} funded with SOL:`, donorKeypair.publicKey.toBase58());
-
-  //     // 创建关联代币账户 (ATA)
-  //     const donorTokenAccount = await getOrCreateAssociatedTokenAccount(
-  //       connection,
-  //       payer,
-  //       mint,
-  //       donorKeypair.publicKey,
-  //     );
-
-  //     // 向捐赠者账户铸造一些代币 (例如1000枚)
-  //     const mintAmount = 1000 * 100; // 考虑到小数位
-  //     await mintTo(
-  //       connection,
-  //       payer,
-  //       mint,
-  //       donorTokenAccount.address,
-  //       payer,
-  //       mintAmount,
-  //     );
-  //     console.log(`Donor ${i + 1} minted tokens:`, donorTokenAccount.address.toBase58());
-
-  //     donors.push({
-  //       keypair: donorKeypair,
-  //       tokenAccount: donorTokenAccount.address,
-  //     });
-  //   };
-
-  //   // 保存 donors 到 donors.json 文件
-  //   const donorsToSave = donors.map(({ keypair, tokenAccount }) => ({
-  //     secretKey: Array.from(keypair.secretKey), // 保存为数组
-  //     publicKey: keypair.publicKey.toBase58(),
-  //     tokenAccount,
-  //   }));
-  //   fs.writeFileSync("donors.json", JSON.stringify(donorsToSave, null, 2), "utf-8");
-  //   console.log("Donor data saved to donors.json");
-  // });
-
-  // it("should initialize the campaign correctly", async () => {
-  //   const now = Math.floor(Date.now() / 1000);
-
-  //   await program.methods.campaign(
-  //     "捐款测试",
-  //     new anchor.BN(20000),
-  //     new anchor.BN(now - 3600),
-  //     new anchor.BN(now + 60)
-  //   ).accounts({
-  //     mint,
-  //     tokenProgram: TOKEN_PROGRAM_ID,
-  //   }).rpc();
-
-  //   // 获取并打印 campaign 数据，确认成功初始化
-  //   const [crowdfundAccountPda] = PublicKey.findProgramAddressSync(
-  //     [provider.wallet.publicKey.toBuffer()],
-  //     program.programId
-  //   );
-
-  //   const campaignData = await program.account.crowdfund.fetch(crowdfundAccountPda);
-  //   console.log("Campaign Data:", campaignData);
-  // });
-
-  // it("should handle multiple concurrent donations correctly", async () => {
-
-  //   const DONATION_AMOUNT = new anchor.BN(45 * 100);
-
-  //   // 构建多个并行的捐款请求
-  //   await Promise.all(
-  //     donors.map(async ({ keypair, tokenAccount }, index) => {
-  //       try {
-  //         const donorProvider = new anchor.AnchorProvider(
-  //           connection,
-  //           new anchor.Wallet(keypair),
-  //           { commitment: "confirmed" }
-  //         );
-  //         const donorProgram = new Program<CrowdFund>(
-  //           program.idl as CrowdFund,
-  //           donorProvider
-  //         );
-
-  //         // const DONATION_AMOUNT = (index % 2 === 0)
-  //         //   ? new anchor.BN(45 * 100)
-  //         //   : new anchor.BN(30 * 100);
-
-  //         // 调用捐款指令
-  //         const tx = await donorProgram.methods
-  //           .donation(DONATION_AMOUNT)
-  //           .accounts({
-  //             donor: keypair.publicKey,
-  //             maker: payer.publicKey,
-  //             mint,
-  //             tokenProgram: TOKEN_PROGRAM_ID,
-  //           })
-  //           .rpc({ commitment: "confirmed" });
-
-  //         console.log(`Donor ${index + 1} donation tx: ${tx}`);
-  //       } catch (error: any) {
-  //         console.error(`Donation for donor ${index + 1} failed: ${error.message}`);
-  //       };
-
-  //     })
-  //   );
-
-  //   const [crowdfundAccountPda] = PublicKey.findProgramAddressSync(
-  //     [provider.wallet.publicKey.toBuffer()],
-  //     program.programId
-  //   );
-
-  //   const campaignData = await program.account.crowdfund.fetch(crowdfundAccountPda);
-  //   console.log("Campaign Data:", campaignData);
-  //   console.log("All donations completed concurrently.");
-  // });
-
-  // it("withdrawal", async () => {
-  //   try {
-  //     await program.methods.withdraw().accounts({
-  //       mint,
-  //       tokenProgram: TOKEN_PROGRAM_ID
-  //     }).rpc()
-
-  //     const [crowdfundAccountPda] = PublicKey.findProgramAddressSync(
-  //       [provider.wallet.publicKey.toBuffer()],
-  //       program.programId
-  //     );
-
-  //     const campaignData = await program.account.crowdfund.fetch(crowdfundAccountPda);
-  //     console.log("Campaign Data:", campaignData); 
-  //   } catch (error: any) {
-  //     console.error("withdrawal err: ", error)
-  //   }
-
-  // })
-
-  // it("refund", async () => {
-  //   await program.methods.finalize().accounts({
-  //     make: payer.publicKey
-  //   }).rpc();
-
-  //   await Promise.all(
-  //     donors.map(async ({ keypair, tokenAccount }, index) => {
-  //       try {
-  //         const donorProvider = new anchor.AnchorProvider(
-  //           connection,
-  //           new anchor.Wallet(keypair),
-  //           { commitment: "confirmed" }
-  //         );
-  //         const donorProgram = new Program<CrowdFund>(
-  //           program.idl as CrowdFund,
-  //           donorProvider
-  //         );
-
-  //         // 每个donor单独计算自己的 donation_record_account PDA
-  //         const [donationRecordAccountPda] = PublicKey.findProgramAddressSync(
-  //           [keypair.publicKey.toBuffer()],
-  //           program.programId
-  //         );
-
-  //         await donorProgram.methods
-  //           .refund()
-  //           .accounts({
-  //             donor: keypair.publicKey,
-  //             weeklyPlanner: payer.publicKey,
-  //             mint,
-  //             tokenProgram: TOKEN_PROGRAM_ID,
-  //           })
-  //           .rpc();
-
-  //         const donorData = await program.account.donationRecord.fetch(donationRecordAccountPda);
-  //         console.log("donorData: ", donorData);
-  //       } catch (error: any) {
-  //         console.error(`Donation for donor ${index + 1} failed: ${error.message}`);
-  //       };
-
-  //     })
-  //   );
-  // })
-
-  // it("save merkle root", async () => {
-  //   const merkleRootBuffer = Buffer.from(
-  //       "7a142ecc0f12a86f764bd2849d216ae1e19c6aaa4a4dda516246a9148cf2eede",
-  //       "hex"
-  //     );
-  //   const merkleRootArray = Array.from(merkleRootBuffer);
-  //   await program.methods.setMerkleRoot(
-  //       merkleRootArray
-  //     ).accounts({
-  //       authority: payer.publicKey
-  //     }).rpc();
-
-  //   const [crowdfundAccountPda] = PublicKey.findProgramAddressSync(
-  //     [provider.wallet.publicKey.toBuffer()],
-  //     program.programId
-  //   );
-
-  //   const campaignData = await program.account.crowdfund.fetch(crowdfundAccountPda);
-  //   console.log("Campaign Data:", campaignData);
-  // });
-
-  it("should allow an eligible donor to claim reward", async () => {
-    // 获取捐赠者列表
-    const donors = loadDonors();
-
-    // 1. 从数据库中读取离线构造好的 Merkle Tree 数据
-    const { merkleRoot, leaves } = await getMerkleTreeData();
-
-    // 使用 merkletreejs 重新构造 Merkle Tree，确保用同样的哈希函数和合并规则
-    const tree = new MerkleTree(leaves, sha256, { sortPairs: true, duplicateOdd: true });
-    // const computedRoot = tree.getRoot().toString("hex");
-    const computedRootArray = Array.from(tree.getRoot());
-    console.log("root: ", computedRootArray)
-    // printMerkleTree(tree);
-
-    // 2. 假设我们选择第一个符合条件的捐赠者（中奖者）
-    const donorInfo = donors[0]; // 此处假设 donors 中的记录都是中奖者
-    // 构造叶子节点：这里的构造方式必须与离线构造时完全一致
-    // 例如离线构造时使用的方式是： leaf_input = "{donor}-{donationAmount}"
-    const leafInput = `${donorInfo.publicKey.toBase58()}-${donorInfo.donationAmount}`;
-    const donorLeaf = sha256(Buffer.from(leafInput, "utf8"));
-
-    // 3. 生成该捐赠者的 Merkle Proof（proof 数组）
-    const proofArray = getMerkleProofForLeaf(tree, donorLeaf);
-    console.log("proof:", proofArray);
-
-    const computedRootFromProof = verifyMerkleProof(donorLeaf, proofArray, tree.getRoot());
-
-    console.log("Computed Root from Proof (hex):", computedRootFromProof.toString("hex"));
+  // 奖励名单的叶子节点，与链上 reward_claim 的构造方式一致："{donor}-{amount}"
+  function donorLeaf(donor: PublicKey, amount: number): Buffer {
+    return sha256(Buffer.from(`${donor.toBase58()}-${amount}`, "utf8"));
+  }
+
+  // 读取链上时钟，众筹的时间窗口都按链上时间判断
+  async function chainNow(): Promise<number> {
+    const clock = await connection.getAccountInfo(SYSVAR_CLOCK_PUBKEY);
+    return new anchor.BN(clock!.data.subarray(32, 40), "le").toNumber();
+  }
 
+  // 等到链上时间超过 timestamp
+  async function waitUntil(timestamp: number) {
+    while ((await chainNow()) <= timestamp) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+  }
+
+  // 断言交易失败，并且错误码或日志中包含 code
+  async function expectError(promise: Promise<unknown>, code: string) {
+    let error: any;
     try {
-      const tx = await program.methods.rewardClaim(proofArray)
+      await promise;
+    } catch (err) {
+      error = err;
+    }
+    expect(error, `expected ${code}`).to.not.be.undefined;
+
+    const text = [error.error?.errorCode?.code, error.message, ...(error.logs ?? [])].join("\n");
+    expect(text).to.include(code);
+  }
+
+  async function newWallet(): Promise<Keypair> {
+    const wallet = Keypair.generate();
+    await fundAccount(payer, wallet.publicKey, AIRDROP_AMOUNT);
+    return wallet;
+  }
+
+  // 创建关联代币账户并铸造代币
+  async function fundTokens(owner: PublicKey, amount: number): Promise<PublicKey> {
+    const tokenAccount = await getOrCreateAssociatedTokenAccount(
+      connection,
+      payer,
+      mint,
+      owner,
+    );
+    if (amount > 0) {
+      await mintTo(connection, payer, mint, tokenAccount.address, payer, amount);
+    }
+    return tokenAccount.address;
+  }
+
+  async function newDonor(amount: number = MINT_AMOUNT): Promise<Keypair> {
+    const donor = await newWallet();
+    await fundTokens(donor.publicKey, amount);
+    return donor;
+  }
+
+  function tokenAccountOf(owner: PublicKey): PublicKey {
+    return getAssociatedTokenAddressSync(mint, owner, true);
+  }
+
+  async function tokenBalance(tokenAccount: PublicKey): Promise<number> {
+    return Number((await getAccount(connection, tokenAccount)).amount);
+  }
+
+  function campaignPda(maker: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("campaign"), maker.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function donationPda(crowdfund: PublicKey, donor: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("donation"), crowdfund.toBuffer(), donor.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // 黑名单条目按钱包派生，未被封禁时该地址没有数据
+  function blockedPda(wallet: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("blocked"), wallet.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  function referrerStatsPda(crowdfund: PublicKey, referrer: PublicKey): PublicKey {
+    return PublicKey.findProgramAddressSync(
+      [Buffer.from("referrer"), crowdfund.toBuffer(), referrer.toBuffer()],
+      program.programId
+    )[0];
+  }
+
+  // 众筹 PDA 由发起人派生，每个众筹使用新的发起人钱包
+  async function createCampaign(options: CampaignOptions = {}): Promise<TestCampaign> {
+    const maker = await newWallet();
+    const startTime = options.startTime ?? (await chainNow()) + START_DELAY;
+    const endTime = startTime + (options.duration ?? CAMPAIGN_DURATION);
+    const crowdfund = campaignPda(maker.publicKey);
+    const escrow = tokenAccountOf(crowdfund);
+    const beneficiary = options.beneficiary ?? maker.publicKey;
+
+    await program.methods
+      .campaign(
+        options.name ?? "捐款测试",
+        new anchor.BN(options.target ?? 20000),
+        new anchor.BN(startTime),
+        new anchor.BN(endTime),
+        options.fundingModel ?? ALL_OR_NOTHING,
+        options.vesting
+          ? { cliff: new anchor.BN(options.vesting.cliff), duration: new anchor.BN(options.vesting.duration) }
+          : null
+      )
+      .accountsPartial({
+        payer: maker.publicKey,
+        beneficiary,
+        mint,
+        crowdfundAccount: crowdfund,
+        campaignTokenAccount: escrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([maker])
+      .rpc();
+
+    return { maker, crowdfund, escrow, beneficiary, startTime, endTime };
+  }
+
+  async function donate(campaign: TestCampaign, donor: Keypair, amount: number, options: DonateOptions = {}) {
+    const rentPayer = options.payer ?? donor;
+    const beneficiary = options.beneficiary ?? donor.publicKey;
+
+    return program.methods
+      .donation(
+        new anchor.BN(amount),
+        options.tier ?? null,
+        options.memo ?? null,
+        options.referrer ?? null,
+        options.allowlistProof ?? null
+      )
+      .accountsPartial({
+        payer: rentPayer.publicKey,
+        tokenAuthority: donor.publicKey,
+        beneficiary,
+        maker: campaign.maker.publicKey,
+        mint,
+        crowdfundAccount: campaign.crowdfund,
+        campaignTokenAccount: campaign.escrow,
+        donationRecordAccount: donationPda(campaign.crowdfund, beneficiary),
+        donationTokenAccount: tokenAccountOf(donor.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        pledgeMint: null,
+        pledgeTokenAccount: null,
+        matchingPool: null,
+        matchingVault: null,
+        round: null,
+        referrerStats: options.referrer ? referrerStatsPda(campaign.crowdfund, options.referrer) : null,
+        reference: options.reference ?? null,
+        ...options.accounts,
+      })
+      .signers(rentPayer === donor ? [donor] : [donor, rentPayer])
+      .rpc();
+  }
+
+  // 结算配捐池时需要传入 mint 及相关代币账户
+  async function finalize(campaign: TestCampaign, accounts: object = {}) {
+    return program.methods
+      .finalize()
+      .accountsPartial({
+        make: campaign.maker.publicKey,
+        crowdfundAccount: campaign.crowdfund,
+        mint: null,
+        matchingPool: null,
+        matchingVault: null,
+        campaignTokenAccount: null,
+        sponsorTokenAccount: null,
+        tokenProgram: null,
+        ...accounts,
+      })
+      .rpc();
+  }
+
+  async function withdraw(
+    campaign: TestCampaign,
+    owner: Keypair = campaign.maker,
+    beneficiary: PublicKey = campaign.beneficiary
+  ) {
+    return program.methods
+      .withdraw()
+      .accountsPartial({
+        owner: owner.publicKey,
+        mint,
+        crowdfundAccount: campaign.crowdfund,
+        beneficiary,
+        withdrawTokenAccount: tokenAccountOf(beneficiary),
+        beneficiaryBlocklist: blockedPda(beneficiary),
+        campaignTokenAccount: campaign.escrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  async function refund(campaign: TestCampaign, donor: PublicKey, accounts: object = {}) {
+    return program.methods
+      .refund()
+      .accountsPartial({
+        donor,
+        weeklyPlanner: campaign.maker.publicKey,
+        mint,
+        crowdfundAccount: campaign.crowdfund,
+        donationRecordAccount: donationPda(campaign.crowdfund, donor),
+        donationTokenAccount: tokenAccountOf(donor),
+        campaignTokenAccount: campaign.escrow,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        badgeMint: null,
+        badgeTokenAccount: null,
+        token2022Program: null,
+        ...accounts,
+      })
+      .rpc();
+  }
+
+  async function pauseCampaign(campaign: TestCampaign, owner: Keypair = campaign.maker) {
+    return program.methods
+      .pauseCampaign()
+      .accountsPartial({
+        owner: owner.publicKey,
+        crowdfundAccount: campaign.crowdfund,
+      })
+      .signers([owner])
+      .rpc();
+  }
+
+  async function resumeCampaign(campaign: TestCampaign, caller: Keypair = campaign.maker) {
+    return program.methods
+      .resumeCampaign()
+      .accountsPartial({
+        caller: caller.publicKey,
+        crowdfundAccount: campaign.crowdfund,
+      })
+      .signers([caller])
+      .rpc();
+  }
+
+  // 仲裁人为 provider 钱包
+  async function setDisputePolicy(disputeWindow: number) {
+    await program.methods
+      .setDisputePolicy(payer.publicKey, new anchor.BN(disputeWindow), DISPUTE_THRESHOLD_BPS)
+      .accountsPartial({ admin: payer.publicKey, config: configPda })
+      .rpc();
+  }
+
+  async function setCampaignLimits(maxCampaignDuration: number, maxPauseDuration: number) {
+    await program.methods
+      .setCampaignLimits(new anchor.BN(maxCampaignDuration), new anchor.BN(maxPauseDuration))
+      .accountsPartial({ admin: payer.publicKey, config: configPda })
+      .rpc();
+  }
+
+  // 支持者取回捐款（撤回、延期后退出）共用的账户
+  function exitAccounts(campaign: TestCampaign, donor: PublicKey) {
+    return {
+      donor,
+      maker: campaign.maker.publicKey,
+      mint,
+      crowdfundAccount: campaign.crowdfund,
+      donationRecordAccount: donationPda(campaign.crowdfund, donor),
+      donationTokenAccount: tokenAccountOf(donor),
+      campaignTokenAccount: campaign.escrow,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      round: null,
+      referrerStats: null,
+      pledgeMint: null,
+      pledgeTokenAccount: null,
+      badgeMint: null,
+      badgeTokenAccount: null,
+      token2022Program: null,
+    };
+  }
+
+  async function unpledge(campaign: TestCampaign, donor: Keypair, amount: number, accounts: object = {}) {
+    return program.methods
+      .unpledge(new anchor.BN(amount))
+      .accountsPartial({ ...exitAccounts(campaign, donor.publicKey), ...accounts })
+      .signers([donor])
+      .rpc();
+  }
+
+  async function fetchCampaign(campaign: TestCampaign) {
+    return program.account.crowdfund.fetch(campaign.crowdfund);
+  }
+
+  // 成功的众筹需等到结束且争议期已过，才能提款或领取推荐奖励
+  async function waitUntilSettled(campaign: TestCampaign) {
+    const succeededAt = (await fetchCampaign(campaign)).succeededAt.toNumber();
+    await waitUntil(Math.max(campaign.endTime, succeededAt + DISPUTE_WINDOW));
+  }
+
+  async function blockWallet(wallet: PublicKey, admin: Keypair = payer) {
+    return program.methods
+      .blockWallet(wallet)
+      .accountsPartial({
+        admin: admin.publicKey,
+        config: configPda,
+        blockedWallet: blockedPda(wallet),
+      })
+      .signers([admin])
+      .rpc();
+  }
+
+  async function unblockWallet(wallet: PublicKey) {
+    return program.methods
+      .unblockWallet()
+      .accountsPartial({
+        admin: payer.publicKey,
+        config: configPda,
+        blockedWallet: blockedPda(wallet),
+      })
+      .rpc();
+  }
+
+  // 全局配置只需初始化一次，必须由程序升级权限账户签名
+  before(async () => {
+    const config = await program.account.config.fetchNullable(configPda);
+    if (!config) {
+      await program.methods.initializeConfig()
         .accounts({
+          authority: payer.publicKey,
+          programData: programDataPda,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    // 缩短各时间窗口，测试中无需等待数天
+    await setCampaignLimits(MAX_CAMPAIGN_DURATION, MAX_PAUSE_DURATION);
+    await program.methods
+      .setExtensionPolicy(3, new anchor.BN(EXTENSION_GRACE_PERIOD))
+      .accountsPartial({ admin: payer.publicKey, config: configPda })
+      .rpc();
+    await program.methods
+      .setBeneficiaryTimelock(new anchor.BN(BENEFICIARY_TIMELOCK))
+      .accountsPartial({ admin: payer.publicKey, config: configPda })
+      .rpc();
+    await setDisputePolicy(DISPUTE_WINDOW);
+  });
+
+  before(async () => {
+    // 1. 创建一个新的 SPL 代币 mint (小数位为2)
+    mint = await createMint(
+      connection,
+      payer,
+      payer.publicKey,
+      null,
+      2,
+    );
+    console.log("Mint created:", mint.toBase58());
+
+    // 2. 创建捐赠者账户，转入SOL，创建关联token账户并铸造代币
+    for (let i = 0; i < NUM_DONORS; i++) {
+      const donorKeypair = await newWallet();
+      const donorTokenAccount = await fundTokens(donorKeypair.publicKey, MINT_AMOUNT);
+
+      donors.push({
+        keypair: donorKeypair,
+        tokenAccount: donorTokenAccount,
+      });
+    };
+  });
+
+  describe("campaign lifecycle", () => {
+    const DONATION_AMOUNT = 45 * 100;
+    let campaign: TestCampaign;
+    let tree: MerkleTree;
+
+    it("should initialize the campaign correctly", async () => {
+      campaign = await createCampaign({ target: 20000 });
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.name).to.equal("捐款测试");
+      expect(campaignData.targetAmount.toNumber()).to.equal(20000);
+      expect(campaignData.startTime.toNumber()).to.equal(campaign.startTime);
+      expect(campaignData.endTime.toNumber()).to.equal(campaign.endTime);
+      expect(campaignData.state).to.equal(0);
+    });
+
+    it("rejects campaigns that start in the past, run too long or have a long name", async () => {
+      const now = await chainNow();
+
+      await expectError(createCampaign({ startTime: now - 3600 }), "StartInPast");
+      await expectError(createCampaign({ duration: 91 * 24 * 60 * 60 }), "DurationTooLong");
+      await expectError(createCampaign({ name: "a".repeat(51) }), "NameTooLong");
+    });
+
+    it("should handle multiple concurrent donations correctly", async () => {
+      await waitUntil(campaign.startTime);
+
+      // 构建多个并行的捐款请求
+      await Promise.all(
+        donors.map(({ keypair }) => donate(campaign, keypair, DONATION_AMOUNT))
+      );
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.raisedAmount.toNumber()).to.equal(DONATION_AMOUNT * NUM_DONORS);
+      expect(campaignData.state).to.equal(1);
+      expect(await tokenBalance(campaign.escrow)).to.equal(DONATION_AMOUNT * NUM_DONORS);
+    });
+
+    it("save merkle root", async () => {
+      // 在测试中按捐赠记录构造获奖名单
+      const leaves = donors.map(({ keypair }) => donorLeaf(keypair.publicKey, DONATION_AMOUNT));
+      tree = new MerkleTree(leaves, sha256, { sortPairs: true, duplicateOdd: true });
+
+      await program.methods.setMerkleRoot(
+        Array.from(tree.getRoot())
+      ).accountsPartial({
+        owner: campaign.maker.publicKey,
+        crowdfundAccount: campaign.crowdfund,
+      }).signers([campaign.maker]).rpc();
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(Buffer.from(campaignData.merkleRoot).equals(tree.getRoot())).to.be.true;
+    });
+
+    it("should allow an eligible donor to claim reward", async () => {
+      const donorInfo = donors[0].keypair;
+      const leaf = donorLeaf(donorInfo.publicKey, DONATION_AMOUNT);
+
+      // 生成该捐赠者的 Merkle Proof（proof 数组）
+      const proofArray = getMerkleProofForLeaf(tree, leaf);
+      expect(verifyMerkleProof(leaf, proofArray, tree.getRoot()).equals(tree.getRoot())).to.be.true;
+
+      await program.methods.rewardClaim(proofArray)
+        .accountsPartial({
           donor: donorInfo.publicKey,
-          maker: payer.publicKey, // 这里假设 maker 为发起人或管理员
-        }).signers([donorInfo.keypair])
+          maker: campaign.maker.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+          donationRecordAccount: donationPda(campaign.crowdfund, donorInfo.publicKey),
+        }).signers([donorInfo])
         .rpc({ commitment: "confirmed" });
-      console.log("Reward claim transaction:", tx);
-    } catch (error: any) {
-      console.error("Reward claim failed:", error);
-      throw error;
+
+      // 使用别人的证明领取会失败
+      const other = donors[1].keypair;
+      await expectError(
+        program.methods.rewardClaim(proofArray)
+          .accountsPartial({
+            donor: other.publicKey,
+            maker: campaign.maker.publicKey,
+            crowdfundAccount: campaign.crowdfund,
+            donationRecordAccount: donationPda(campaign.crowdfund, other.publicKey),
+          }).signers([other])
+          .rpc(),
+        "InvalidMerkleProof"
+      );
+    });
+
+    it("withdrawal", async () => {
+      await waitUntilSettled(campaign);
+
+      await withdraw(campaign);
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.isWithdrawals).to.be.true;
+      expect(await tokenBalance(tokenAccountOf(campaign.maker.publicKey))).to.equal(DONATION_AMOUNT * NUM_DONORS);
+      expect(await tokenBalance(campaign.escrow)).to.equal(0);
+    });
+
+    it("refund", async () => {
+      // 未达到目标的众筹失败后，捐赠者可以退款
+      const failed = await createCampaign({ target: 1_000_000 });
+      const refunders = donors.slice(0, 2).map(({ keypair }) => keypair);
+      const balances = await Promise.all(refunders.map((donor) => tokenBalance(tokenAccountOf(donor.publicKey))));
+
+      await waitUntil(failed.startTime);
+      for (const donor of refunders) {
+        await donate(failed, donor, 1000);
+      }
+
+      await expectError(refund(failed, refunders[0].publicKey), "RefundNotAllowed");
+
+      await waitUntil(failed.endTime);
+      await finalize(failed);
+      expect((await fetchCampaign(failed)).state).to.equal(2);
+
+      for (const [index, donor] of refunders.entries()) {
+        await refund(failed, donor.publicKey);
+
+        const record = await program.account.donationRecord.fetch(donationPda(failed.crowdfund, donor.publicKey));
+        expect(record.isRefunded).to.be.true;
+        expect(await tokenBalance(tokenAccountOf(donor.publicKey))).to.equal(balances[index]);
+      }
+
+      await expectError(refund(failed, refunders[0].publicKey), "AlreadyRefunded");
+    });
+  });
+
+  describe("pause and resume", () => {
+    it("lets only the owner pause, and resume before the pause limit", async () => {
+      const campaign = await createCampaign();
+      const stranger = await newWallet();
+
+      await expectError(pauseCampaign(campaign, stranger), "Unauthorized");
+
+      await pauseCampaign(campaign);
+      expect((await fetchCampaign(campaign)).isPaused).to.be.true;
+      await expectError(resumeCampaign(campaign, stranger), "Unauthorized");
+
+      await resumeCampaign(campaign);
+
+      // 暂停时长计入结束时间，最多顺延暂停上限
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.isPaused).to.be.false;
+      expect(campaignData.endTime.toNumber() - campaign.endTime).to.be.within(0, MAX_PAUSE_DURATION);
+    });
+
+    it("resumes a campaign created at the max duration after a long pause", async () => {
+      const duration = 20;
+      await setCampaignLimits(duration, MAX_PAUSE_DURATION);
+
+      try {
+        const campaign = await createCampaign({ duration });
+        const stranger = await newWallet();
+
+        await pauseCampaign(campaign);
+        const pausedAt = (await fetchCampaign(campaign)).pausedAt.toNumber();
+
+        // 暂停超过上限后任何人都可以恢复；总时长已到上限，不再顺延也不会失败
+        await waitUntil(pausedAt + MAX_PAUSE_DURATION);
+        await resumeCampaign(campaign, stranger);
+
+        const campaignData = await fetchCampaign(campaign);
+        expect(campaignData.isPaused).to.be.false;
+        expect(campaignData.endTime.toNumber()).to.equal(campaign.endTime);
+      } finally {
+        await setCampaignLimits(MAX_CAMPAIGN_DURATION, MAX_PAUSE_DURATION);
+      }
+    });
+  });
+
+  describe("campaign metadata", () => {
+    const IMAGE_HASH = Array.from(sha256(Buffer.from("image")));
+
+    async function updateMetadata(campaign: TestCampaign, owner: Keypair, name: string, metadataUri: string) {
+      return program.methods
+        .updateCampaignMetadata(name, metadataUri, 3, IMAGE_HASH)
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    it("lets the owner update the name, uri, category and image hash", async () => {
+      const campaign = await createCampaign();
+      // 超过创建时预留长度的名称会扩容账户
+      const name = "n".repeat(80);
+      const metadataUri = "https://example.com/campaign.json";
+
+      await updateMetadata(campaign, campaign.maker, name, metadataUri);
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.name).to.equal(name);
+      expect(campaignData.metadataUri).to.equal(metadataUri);
+      expect(campaignData.category).to.equal(3);
+      expect(campaignData.imageHash).to.deep.equal(IMAGE_HASH);
+    });
+
+    it("rejects updates from other wallets and oversized fields", async () => {
+      const campaign = await createCampaign();
+      const stranger = await newWallet();
+
+      await expectError(updateMetadata(campaign, stranger, "name", ""), "Unauthorized");
+      await expectError(updateMetadata(campaign, campaign.maker, "n".repeat(101), ""), "NameTooLong");
+      await expectError(updateMetadata(campaign, campaign.maker, "name", "u".repeat(201)), "MetadataUriTooLong");
+    });
+  });
+
+  describe("deadline changes", () => {
+    async function extendDeadline(campaign: TestCampaign, owner: Keypair, newEndTime: number) {
+      return program.methods
+        .extendDeadline(new anchor.BN(newEndTime))
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    async function exitAfterExtension(campaign: TestCampaign, donor: Keypair) {
+      return program.methods
+        .exitAfterExtension()
+        .accountsPartial(exitAccounts(campaign, donor.publicKey))
+        .signers([donor])
+        .rpc();
+    }
+
+    it("lets the owner move the deadline and earlier backers exit during the grace period", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const early = await newDonor();
+      const late = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, early, 1000);
+      const record = await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, early.publicKey));
+      // 只有截止时间变更之前的捐款可以退出
+      await waitUntil(record.donatedAt.toNumber());
+
+      const newEndTime = campaign.endTime + 10;
+      await extendDeadline(campaign, campaign.maker, newEndTime);
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.endTime.toNumber()).to.equal(newEndTime);
+      expect(campaignData.extensionCount).to.equal(1);
+
+      await donate(campaign, late, 1000);
+      await expectError(exitAfterExtension(campaign, late), "ExitNotAllowed");
+
+      const balance = await tokenBalance(tokenAccountOf(early.publicKey));
+      await exitAfterExtension(campaign, early);
+
+      expect(await tokenBalance(tokenAccountOf(early.publicKey))).to.equal(balance + 1000);
+      expect((await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, early.publicKey))).isRefunded).to.be.true;
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(1000);
+      await expectError(exitAfterExtension(campaign, early), "AlreadyRefunded");
+    });
+
+    it("rejects deadline changes from other wallets, to the same deadline or past the max duration", async () => {
+      const campaign = await createCampaign();
+      const stranger = await newWallet();
+
+      await expectError(extendDeadline(campaign, stranger, campaign.endTime + 10), "Unauthorized");
+      await expectError(extendDeadline(campaign, campaign.maker, campaign.endTime), "InvalidDeadline");
+      await expectError(
+        extendDeadline(campaign, campaign.maker, campaign.startTime + MAX_CAMPAIGN_DURATION + 1),
+        "DurationTooLong"
+      );
+    });
+  });
+
+  describe("unpledge", () => {
+    it("lets a donor take back part or all of a pledge and donate again", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const tokenAccount = tokenAccountOf(donor.publicKey);
+      const record = donationPda(campaign.crowdfund, donor.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+      const balance = await tokenBalance(tokenAccount);
+
+      await unpledge(campaign, donor, 400);
+      expect((await program.account.donationRecord.fetch(record)).amount.toNumber()).to.equal(600);
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(600);
+      expect(await tokenBalance(tokenAccount)).to.equal(balance + 400);
+
+      // 全部撤回后捐赠记录关闭，可以重新捐款
+      await unpledge(campaign, donor, 600);
+      expect(await program.account.donationRecord.fetchNullable(record)).to.be.null;
+      expect(await tokenBalance(tokenAccount)).to.equal(balance + 1000);
+
+      await donate(campaign, donor, 500);
+      expect((await program.account.donationRecord.fetch(record)).amount.toNumber()).to.equal(500);
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(500);
+    });
+
+    it("rejects unpledging more than the pledge or after the campaign ends", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+
+      await expectError(unpledge(campaign, donor, 1001), "InsufficientPledge");
+
+      await waitUntil(campaign.endTime);
+      await expectError(unpledge(campaign, donor, 100), "CampaignExpired");
+    });
+  });
+
+  describe("keep-it-all funding", () => {
+    it("pays out whatever was raised once the campaign ends and refuses refunds", async () => {
+      const campaign = await createCampaign({ target: 1_000_000, fundingModel: KEEP_IT_ALL });
+      const donor = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+      expect((await fetchCampaign(campaign)).state).to.equal(0);
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      expect((await fetchCampaign(campaign)).state).to.equal(1);
+
+      await expectError(refund(campaign, donor.publicKey), "RefundNotAllowed");
+
+      // 灵活众筹在结算时才成功，争议期从此时开始
+      await waitUntilSettled(campaign);
+      await withdraw(campaign);
+      expect(await tokenBalance(tokenAccountOf(campaign.maker.publicKey))).to.equal(1000);
+    });
+
+    it("rejects unknown funding models and fails keep-it-all campaigns that raised nothing", async () => {
+      await expectError(createCampaign({ fundingModel: 2 }), "InvalidFundingModel");
+
+      const campaign = await createCampaign({ fundingModel: KEEP_IT_ALL });
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+
+      expect((await fetchCampaign(campaign)).state).to.equal(2);
+      await expectError(withdraw(campaign), "WithdrawalNotAllowed");
+    });
+  });
+
+  describe("reward tiers", () => {
+    async function addRewardTier(campaign: TestCampaign, owner: Keypair, minPledge: number, maxQuantity: number) {
+      return program.methods
+        .addRewardTier(new anchor.BN(minPledge), maxQuantity)
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    it("reserves limited tier units for pledges above the minimum", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const first = await newDonor();
+      const second = await newDonor();
+
+      await addRewardTier(campaign, campaign.maker, 500, 1);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, first, 600, { tier: 0 });
+      expect((await fetchCampaign(campaign)).rewardTiers[0].claimed).to.equal(1);
+      expect((await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, first.publicKey))).tier).to.equal(0);
+
+      await expectError(donate(campaign, second, 600, { tier: 0 }), "RewardTierSoldOut");
+
+      // 撤回捐款后名额释放给其他支持者
+      await unpledge(campaign, first, 600);
+      expect((await fetchCampaign(campaign)).rewardTiers[0].claimed).to.equal(0);
+
+      await donate(campaign, second, 600, { tier: 0 });
+      expect((await fetchCampaign(campaign)).rewardTiers[0].claimed).to.equal(1);
+    });
+
+    it("rejects tiers added by other wallets, pledges below the minimum and unknown tiers", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const stranger = await newWallet();
+      const donor = await newDonor();
+
+      await expectError(addRewardTier(campaign, stranger, 500, 1), "Unauthorized");
+      await addRewardTier(campaign, campaign.maker, 500, 5);
+
+      await waitUntil(campaign.startTime);
+      await expectError(donate(campaign, donor, 100, { tier: 0 }), "PledgeBelowTierMinimum");
+      await expectError(donate(campaign, donor, 600, { tier: 1 }), "InvalidRewardTier");
+    });
+  });
+
+  describe("backer badges", () => {
+    // 徽章铸币带元数据扩展，需要提高计算单元上限
+    const BADGE_COMPUTE_UNITS = 400_000;
+
+    function badgeAccounts(campaign: TestCampaign, donor: PublicKey) {
+      const [badgeMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from("badge"), donationPda(campaign.crowdfund, donor).toBuffer()],
+        program.programId
+      );
+      return {
+        badgeMint,
+        badgeTokenAccount: getAssociatedTokenAddressSync(badgeMint, donor, false, TOKEN_2022_PROGRAM_ID),
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+      };
+    }
+
+    async function mintBackerBadge(campaign: TestCampaign, donor: Keypair) {
+      return program.methods
+        .mintBackerBadge()
+        .accountsPartial({
+          donor: donor.publicKey,
+          maker: campaign.maker.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+          donationRecordAccount: donationPda(campaign.crowdfund, donor.publicKey),
+          ...badgeAccounts(campaign, donor.publicKey),
+        })
+        .preInstructions([ComputeBudgetProgram.setComputeUnitLimit({ units: BADGE_COMPUTE_UNITS })])
+        .signers([donor])
+        .rpc();
+    }
+
+    async function badgeBalance(campaign: TestCampaign, donor: PublicKey): Promise<number> {
+      const { badgeTokenAccount } = badgeAccounts(campaign, donor);
+      return Number((await getAccount(connection, badgeTokenAccount, undefined, TOKEN_2022_PROGRAM_ID)).amount);
+    }
+
+    it("mints one badge per pledge and burns it when the pledge is fully taken back", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const { badgeMint } = badgeAccounts(campaign, donor.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+
+      await mintBackerBadge(campaign, donor);
+      expect(await badgeBalance(campaign, donor.publicKey)).to.equal(1);
+      expect((await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, donor.publicKey))).hasBadge).to.be.true;
+
+      // 全部撤回时销毁徽章并关闭铸币账户与捐赠记录
+      await unpledge(campaign, donor, 1000, badgeAccounts(campaign, donor.publicKey));
+      expect(await connection.getAccountInfo(badgeMint)).to.be.null;
+      expect(await program.account.donationRecord.fetchNullable(donationPda(campaign.crowdfund, donor.publicKey))).to.be.null;
+      expect(await badgeBalance(campaign, donor.publicKey)).to.equal(0);
+
+      // 重新捐款后可以在同一地址再次铸造徽章
+      await donate(campaign, donor, 500);
+      await mintBackerBadge(campaign, donor);
+      expect(await badgeBalance(campaign, donor.publicKey)).to.equal(1);
+    });
+
+    it("rejects a second badge and a full unpledge without the badge accounts", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+      await mintBackerBadge(campaign, donor);
+
+      await expectError(mintBackerBadge(campaign, donor), "BadgeAlreadyMinted");
+      await expectError(unpledge(campaign, donor, 1000), "InvalidBadgeAccounts");
+
+      // 部分撤回不涉及徽章
+      await unpledge(campaign, donor, 400);
+      expect(await badgeBalance(campaign, donor.publicKey)).to.equal(1);
+    });
+  });
+
+  describe("pledge tokens", () => {
+    function pledgeMintOf(campaign: TestCampaign): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("pledge_mint"), campaign.crowdfund.toBuffer()],
+        program.programId
+      )[0];
+    }
+
+    async function enablePledgeTokens(campaign: TestCampaign, owner: Keypair = campaign.maker) {
+      return program.methods
+        .enablePledgeTokens()
+        .accountsPartial({
+          owner: owner.publicKey,
+          mint,
+          crowdfundAccount: campaign.crowdfund,
+          pledgeMint: pledgeMintOf(campaign),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    async function pledgeTokenAccountOf(campaign: TestCampaign, owner: PublicKey): Promise<PublicKey> {
+      const tokenAccount = await getOrCreateAssociatedTokenAccount(connection, payer, pledgeMintOf(campaign), owner);
+      return tokenAccount.address;
+    }
+
+    async function redeemPledgeTokens(campaign: TestCampaign, holder: Keypair, amount: number, accounts: object = {}) {
+      return program.methods
+        .redeemPledgeTokens(new anchor.BN(amount))
+        .accountsPartial({
+          holder: holder.publicKey,
+          maker: campaign.maker.publicKey,
+          mint,
+          crowdfundAccount: campaign.crowdfund,
+          pledgeMint: pledgeMintOf(campaign),
+          holderPledgeTokenAccount: getAssociatedTokenAddressSync(pledgeMintOf(campaign), holder.publicKey),
+          holderTokenAccount: tokenAccountOf(holder.publicKey),
+          campaignTokenAccount: campaign.escrow,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          frozenRedemption: null,
+          ...accounts,
+        })
+        .signers([holder])
+        .rpc();
+    }
+
+    it("lets whoever holds the pledge tokens redeem them after the campaign fails", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const holder = await newWallet();
+
+      await enablePledgeTokens(campaign);
+      const donorPledgeAccount = await pledgeTokenAccountOf(campaign, donor.publicKey);
+      const holderPledgeAccount = await pledgeTokenAccountOf(campaign, holder.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000, {
+        accounts: { pledgeMint: pledgeMintOf(campaign), pledgeTokenAccount: donorPledgeAccount },
+      });
+      expect(await tokenBalance(donorPledgeAccount)).to.equal(1000);
+
+      // 凭证可以自由转让，由持有人赎回
+      await transfer(connection, payer, donorPledgeAccount, holderPledgeAccount, donor, 1000);
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      expect((await fetchCampaign(campaign)).state).to.equal(2);
+
+      await redeemPledgeTokens(campaign, holder, 1000);
+      expect(await tokenBalance(tokenAccountOf(holder.publicKey))).to.equal(1000);
+      expect(await tokenBalance(holderPledgeAccount)).to.equal(0);
+      expect(await tokenBalance(campaign.escrow)).to.equal(0);
+    });
+
+    it("rejects donor refunds, donations without pledge accounts and late or foreign enabling", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const stranger = await newWallet();
+
+      await expectError(enablePledgeTokens(campaign, stranger), "Unauthorized");
+      await enablePledgeTokens(campaign);
+
+      await waitUntil(campaign.startTime);
+      await expectError(donate(campaign, donor, 1000), "InvalidPledgeAccounts");
+      await donate(campaign, donor, 1000, {
+        accounts: {
+          pledgeMint: pledgeMintOf(campaign),
+          pledgeTokenAccount: await pledgeTokenAccountOf(campaign, donor.publicKey),
+        },
+      });
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      // 启用凭证后只能凭凭证赎回
+      await expectError(refund(campaign, donor.publicKey), "PledgeTokensEnabled");
+
+      // 已有捐款的众筹不能再启用凭证
+      const funded = await createCampaign({ target: 1_000_000 });
+      await waitUntil(funded.startTime);
+      await donate(funded, donor, 1000);
+      await expectError(enablePledgeTokens(funded), "PledgeTokensLocked");
+    });
+
+    it("freezes the redemption of a blocked holder and pays it out once unblocked", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const holder = await newDonor();
+      const holderTokenAccount = tokenAccountOf(holder.publicKey);
+      const [frozenRedemption] = PublicKey.findProgramAddressSync(
+        [Buffer.from("frozen_redemption"), campaign.crowdfund.toBuffer(), holder.publicKey.toBuffer()],
+        program.programId
+      );
+
+      await enablePledgeTokens(campaign);
+      const pledgeTokenAccount = await pledgeTokenAccountOf(campaign, holder.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, holder, 1000, { accounts: { pledgeMint: pledgeMintOf(campaign), pledgeTokenAccount } });
+      const balance = await tokenBalance(holderTokenAccount);
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      await blockWallet(holder.publicKey);
+
+      // 被封禁时必须传入冻结记录，凭证照常销毁但不付款
+      await expectError(redeemPledgeTokens(campaign, holder, 600), "InvalidPledgeAccounts");
+      await redeemPledgeTokens(campaign, holder, 600, { frozenRedemption });
+      expect((await program.account.frozenRedemption.fetch(frozenRedemption)).amount.toNumber()).to.equal(600);
+      expect(await tokenBalance(pledgeTokenAccount)).to.equal(400);
+      expect(await tokenBalance(holderTokenAccount)).to.equal(balance);
+      await expectError(redeemPledgeTokens(campaign, holder, 0, { frozenRedemption }), "Blocked");
+
+      // 解封后领取冻结的部分，记录随之关闭
+      await unblockWallet(holder.publicKey);
+      await redeemPledgeTokens(campaign, holder, 0, { frozenRedemption });
+      expect(await tokenBalance(holderTokenAccount)).to.equal(balance + 600);
+      expect(await program.account.frozenRedemption.fetchNullable(frozenRedemption)).to.be.null;
+
+      await redeemPledgeTokens(campaign, holder, 400);
+      expect(await tokenBalance(holderTokenAccount)).to.equal(balance + 1000);
+    });
+  });
+
+  describe("donation memos", () => {
+    // 与链上 MAX_MEMO_LEN 一致
+    const MAX_MEMO_LEN = 128;
+
+    it("records the memo in the event and the reference key in the transaction", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const reference = Keypair.generate().publicKey;
+      const memo = Buffer.from("为社区图书馆加油", "utf8");
+
+      await waitUntil(campaign.startTime);
+      const signature = await donate(campaign, donor, 1000, { memo, reference });
+      await connection.confirmTransaction(signature, "confirmed");
+
+      // 链下按 reference 查找交易
+      const tx = await connection.getTransaction(signature, {
+        commitment: "confirmed",
+        maxSupportedTransactionVersion: 0,
+      });
+      const accountKeys = tx!.transaction.message.getAccountKeys().staticAccountKeys;
+      expect(accountKeys.some((key) => key.equals(reference))).to.be.true;
+
+      const parser = new anchor.EventParser(program.programId, program.coder);
+      const events = Array.from(parser.parseLogs(tx!.meta!.logMessages!));
+      const donation = events.find((event) => event.name === "donationMade");
+      expect(donation, "DonationMade event").to.not.be.undefined;
+      expect(Buffer.from(donation!.data.memo).toString("utf8")).to.equal("为社区图书馆加油");
+      expect(donation!.data.reference.equals(reference)).to.be.true;
+    });
+
+    it("rejects memos longer than the limit", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await expectError(
+        donate(campaign, donor, 1000, { memo: Buffer.alloc(MAX_MEMO_LEN + 1, 1) }),
+        "MemoTooLong"
+      );
+      await donate(campaign, donor, 1000, { memo: Buffer.alloc(MAX_MEMO_LEN, 1) });
+    });
+  });
+
+  describe("donations on behalf of others", () => {
+    it("records the pledge for the beneficiary while another wallet funds it and pays the rent", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const funder = await newDonor();
+      const rentPayer = await newWallet();
+      const beneficiary = await newWallet();
+      // 退款转入归属人的代币账户
+      const beneficiaryTokenAccount = await fundTokens(beneficiary.publicKey, 0);
+      const funderBalance = await tokenBalance(tokenAccountOf(funder.publicKey));
+      const rentPayerLamports = await connection.getBalance(rentPayer.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, funder, 1000, { payer: rentPayer, beneficiary: beneficiary.publicKey });
+
+      const record = await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, beneficiary.publicKey));
+      expect(record.donor.equals(beneficiary.publicKey)).to.be.true;
+      expect(record.amount.toNumber()).to.equal(1000);
+      expect(await program.account.donationRecord.fetchNullable(donationPda(campaign.crowdfund, funder.publicKey))).to.be.null;
+      expect(await tokenBalance(tokenAccountOf(funder.publicKey))).to.equal(funderBalance - 1000);
+      expect(await connection.getBalance(rentPayer.publicKey)).to.be.below(rentPayerLamports);
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      await refund(campaign, beneficiary.publicKey);
+      expect(await tokenBalance(beneficiaryTokenAccount)).to.equal(1000);
+      expect(await tokenBalance(tokenAccountOf(funder.publicKey))).to.equal(funderBalance - 1000);
+    });
+
+    it("rejects a second record for the same beneficiary", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const funder = await newDonor();
+      const beneficiary = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, funder, 1000, { beneficiary: beneficiary.publicKey });
+
+      // 捐赠记录按归属人派生，每个众筹只能有一条
+      await expectError(donate(campaign, funder, 500, { beneficiary: beneficiary.publicKey }), "already in use");
+      await expectError(donate(campaign, beneficiary, 500), "already in use");
+    });
+  });
+
+  describe("matching pools", () => {
+    function poolAccounts(campaign: TestCampaign) {
+      const [matchingPool] = PublicKey.findProgramAddressSync(
+        [Buffer.from("matching_pool"), campaign.crowdfund.toBuffer()],
+        program.programId
+      );
+      return { matchingPool, matchingVault: tokenAccountOf(matchingPool) };
+    }
+
+    async function createMatchingPool(
+      campaign: TestCampaign,
+      sponsor: Keypair,
+      owner: Keypair,
+      ratioBps: number,
+      cap: number,
+      deposit: number
+    ) {
+      return program.methods
+        .createMatchingPool(ratioBps, new anchor.BN(cap), new anchor.BN(deposit))
+        .accountsPartial({
+          sponsor: sponsor.publicKey,
+          owner: owner.publicKey,
+          maker: campaign.maker.publicKey,
+          mint,
+          crowdfundAccount: campaign.crowdfund,
+          campaignTokenAccount: campaign.escrow,
+          ...poolAccounts(campaign),
+          sponsorTokenAccount: tokenAccountOf(sponsor.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([sponsor, owner])
+        .rpc();
+    }
+
+    it("matches donations from the pool and returns the sponsor's funds when the campaign fails", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const sponsor = await newDonor();
+      const donor = await newDonor();
+      const { matchingPool, matchingVault } = poolAccounts(campaign);
+      const sponsorBalance = await tokenBalance(tokenAccountOf(sponsor.publicKey));
+
+      // 按 50% 配捐，上限 1000
+      await createMatchingPool(campaign, sponsor, campaign.maker, 5000, 1000, 1000);
+      expect(await tokenBalance(matchingVault)).to.equal(1000);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 400, { accounts: { matchingPool, matchingVault } });
+
+      const record = await program.account.donationRecord.fetch(donationPda(campaign.crowdfund, donor.publicKey));
+      expect(record.matchedAmount.toNumber()).to.equal(200);
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(600);
+      expect(await tokenBalance(matchingVault)).to.equal(800);
+
+      // 失败后结算，全部配捐与池中余额退回赞助方
+      await waitUntil(campaign.endTime);
+      await finalize(campaign, {
+        mint,
+        matchingPool,
+        matchingVault,
+        campaignTokenAccount: campaign.escrow,
+        sponsorTokenAccount: tokenAccountOf(sponsor.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+
+      expect((await fetchCampaign(campaign)).state).to.equal(2);
+      expect((await program.account.matchingPool.fetch(matchingPool)).isSettled).to.be.true;
+      expect(await tokenBalance(tokenAccountOf(sponsor.publicKey))).to.equal(sponsorBalance);
+      expect(await tokenBalance(campaign.escrow)).to.equal(400);
+    });
+
+    it("rejects pools attached without the owner, invalid ratios and donations that skip the pool", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const sponsor = await newDonor();
+      const stranger = await newWallet();
+      const donor = await newDonor();
+
+      // 配捐池必须由众筹所有者共同签名
+      await expectError(createMatchingPool(campaign, sponsor, stranger, 5000, 1000, 1000), "Unauthorized");
+      await expectError(createMatchingPool(campaign, sponsor, campaign.maker, 0, 1000, 1000), "InvalidMatchingPool");
+      await expectError(createMatchingPool(campaign, sponsor, campaign.maker, 10001, 1000, 1000), "InvalidMatchingPool");
+      expect((await fetchCampaign(campaign)).matchingPool).to.be.null;
+
+      await createMatchingPool(campaign, sponsor, campaign.maker, 5000, 1000, 1000);
+
+      await waitUntil(campaign.startTime);
+      await expectError(donate(campaign, donor, 400), "InvalidMatchingPool");
+    });
+  });
+
+  describe("quadratic funding rounds", () => {
+    function roundPda(roundId: anchor.BN): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("round"), roundId.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    }
+
+    // 轮次 PDA 按编号派生，每个测试使用随机编号
+    async function createRound(endTime: number) {
+      const roundId = new anchor.BN(crypto.randomBytes(8));
+      const round = roundPda(roundId);
+      const roundVault = tokenAccountOf(round);
+
+      await program.methods
+        .createRound(roundId, new anchor.BN(await chainNow()), new anchor.BN(endTime))
+        .accountsPartial({
+          admin: payer.publicKey,
+          config: configPda,
+          mint,
+          round,
+          roundVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      return { round, roundVault };
+    }
+
+    async function fundRound(round: PublicKey, funder: Keypair, amount: number) {
+      return program.methods
+        .fundRound(new anchor.BN(amount))
+        .accountsPartial({
+          funder: funder.publicKey,
+          mint,
+          round,
+          roundVault: tokenAccountOf(round),
+          funderTokenAccount: tokenAccountOf(funder.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([funder])
+        .rpc();
+    }
+
+    async function addRoundCampaign(round: PublicKey, campaign: TestCampaign) {
+      return program.methods
+        .addRoundCampaign()
+        .accountsPartial({
+          admin: payer.publicKey,
+          config: configPda,
+          round,
+          crowdfundAccount: campaign.crowdfund,
+          campaignTokenAccount: campaign.escrow,
+        })
+        .rpc();
+    }
+
+    // remaining accounts 按加入轮次的顺序传入 [众筹账户, 托管账户]，均需可写
+    async function settleRound(round: PublicKey, campaigns: TestCampaign[]) {
+      return program.methods
+        .settleRound()
+        .accountsPartial({
+          config: configPda,
+          mint,
+          round,
+          roundVault: tokenAccountOf(round),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(campaigns.flatMap((campaign) => [
+          { pubkey: campaign.crowdfund, isSigner: false, isWritable: true },
+          { pubkey: campaign.escrow, isSigner: false, isWritable: true },
+        ]))
+        .rpc();
+    }
+
+    async function sweepRound(round: PublicKey) {
+      return program.methods
+        .sweepRound()
+        .accountsPartial({
+          admin: payer.publicKey,
+          config: configPda,
+          mint,
+          round,
+          roundVault: tokenAccountOf(round),
+          adminTokenAccount: tokenAccountOf(payer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    }
+
+    it("returns the whole pot to the admin when every campaign in the round fails", async () => {
+      const first = await createCampaign({ target: 1_000_000 });
+      const second = await createCampaign({ target: 1_000_000 });
+      const funder = await newDonor();
+      const donor = await newDonor();
+      const adminTokenAccount = await fundTokens(payer.publicKey, 0);
+
+      const { round, roundVault } = await createRound(second.endTime);
+      await fundRound(round, funder, 1000);
+      await addRoundCampaign(round, first);
+      await addRoundCampaign(round, second);
+
+      await waitUntil(second.startTime);
+      await donate(first, donor, 400, { accounts: { round } });
+      await donate(second, donor, 900, { accounts: { round } });
+      expect((await program.account.round.fetch(round)).campaigns[0].sumContributions.toNumber()).to.equal(400);
+
+      await waitUntil(second.endTime);
+      await finalize(first);
+      await finalize(second);
+      expect((await fetchCampaign(first)).state).to.equal(2);
+      expect((await fetchCampaign(second)).state).to.equal(2);
+
+      // 失败的众筹不参与分配，奖池原样留在轮次中
+      await settleRound(round, [first, second]);
+      const roundData = await program.account.round.fetch(round);
+      expect(roundData.isSettled).to.be.true;
+      expect(roundData.campaigns.map((entry) => entry.matchAmount.toNumber())).to.deep.equal([0, 0]);
+      expect(await tokenBalance(roundVault)).to.equal(1000);
+      expect(await tokenBalance(first.escrow)).to.equal(400);
+
+      const adminBalance = await tokenBalance(adminTokenAccount);
+      await sweepRound(round);
+      expect(await tokenBalance(adminTokenAccount)).to.equal(adminBalance + 1000);
+      expect(await tokenBalance(roundVault)).to.equal(0);
+    });
+
+    it("rejects donations for other beneficiaries, early settlement and sweeping an empty pot", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const funder = await newDonor();
+      const donor = await newDonor();
+      const beneficiary = await newWallet();
+
+      const { round } = await createRound(campaign.endTime);
+      await fundRound(round, funder, 1000);
+      await addRoundCampaign(round, campaign);
+
+      await waitUntil(campaign.startTime);
+      // 轮次按实际出资人计算权重，不能代他人计入
+      await expectError(
+        donate(campaign, donor, 400, { beneficiary: beneficiary.publicKey, accounts: { round } }),
+        "RoundFunderMismatch"
+      );
+      await donate(campaign, donor, 400, { accounts: { round } });
+
+      await expectError(settleRound(round, [campaign]), "RoundNotSettleable");
+      await expectError(sweepRound(round), "RoundNotSettleable");
+
+      await waitUntil(campaign.endTime);
+      await finalize(campaign);
+      await settleRound(round, [campaign]);
+      await sweepRound(round);
+
+      await expectError(sweepRound(round), "InvalidDonationAmount");
+      await expectError(settleRound(round, [campaign]), "RoundNotSettleable");
+    });
+  });
+
+  describe("stretch goals", () => {
+    async function addStretchGoal(campaign: TestCampaign, owner: Keypair, target: number) {
+      return program.methods
+        .addStretchGoal(new anchor.BN(target), Array.from(sha256(Buffer.from(`goal-${target}`))))
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    it("keeps a funded campaign open for stretch goals until the deadline", async () => {
+      const campaign = await createCampaign({ target: 1000, duration: 20 });
+      const first = await newDonor();
+      const second = await newDonor();
+
+      await addStretchGoal(campaign, campaign.maker, 2000);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, first, 1000);
+      expect((await fetchCampaign(campaign)).state).to.equal(1);
+
+      // 达到主目标后仍可追加延伸目标并继续接受捐款
+      await addStretchGoal(campaign, campaign.maker, 3000);
+      await donate(campaign, second, 1500);
+
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.stretchGoals.map((goal) => goal.target.toNumber())).to.deep.equal([2000, 3000]);
+      expect(campaignData.stretchGoalsReached).to.equal(1);
+      expect(campaignData.raisedAmount.toNumber()).to.equal(2500);
+
+      // 争议期已过但截止前仍不能提取
+      await waitUntil(campaignData.succeededAt.toNumber() + DISPUTE_WINDOW);
+      await expectError(withdraw(campaign), "WithdrawalNotAllowed");
+
+      await waitUntilSettled(campaign);
+      await withdraw(campaign);
+      expect(await tokenBalance(tokenAccountOf(campaign.maker.publicKey))).to.equal(2500);
+    });
+
+    it("rejects goals from other wallets, goals that do not increase and goals after the deadline", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const stranger = await newWallet();
+      const donor = await newDonor();
+
+      await expectError(addStretchGoal(campaign, stranger, 2000), "Unauthorized");
+      await expectError(addStretchGoal(campaign, campaign.maker, 1000), "InvalidStretchGoal");
+      await addStretchGoal(campaign, campaign.maker, 2000);
+      await expectError(addStretchGoal(campaign, campaign.maker, 1500), "InvalidStretchGoal");
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 2500);
+      // 不能低于已募集金额
+      await expectError(addStretchGoal(campaign, campaign.maker, 2400), "InvalidStretchGoal");
+
+      await waitUntil(campaign.endTime);
+      await expectError(addStretchGoal(campaign, campaign.maker, 5000), "CampaignExpired");
+    });
+  });
+
+  describe("referrals", () => {
+    async function setReferralShare(campaign: TestCampaign, owner: Keypair, referralShareBps: number) {
+      return program.methods
+        .setReferralShare(referralShareBps)
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    async function claimReferralReward(campaign: TestCampaign, referrer: Keypair) {
+      return program.methods
+        .claimReferralReward()
+        .accountsPartial({
+          referrer: referrer.publicKey,
+          maker: campaign.maker.publicKey,
+          mint,
+          crowdfundAccount: campaign.crowdfund,
+          referrerStats: referrerStatsPda(campaign.crowdfund, referrer.publicKey),
+          campaignTokenAccount: campaign.escrow,
+          referrerTokenAccount: tokenAccountOf(referrer.publicKey),
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([referrer])
+        .rpc();
+    }
+
+    it("pays referrers their share once the campaign has succeeded", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const donor = await newDonor();
+      const referrer = await newWallet();
+
+      // 10% 推荐奖励
+      await setReferralShare(campaign, campaign.maker, 1000);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 2000, { referrer: referrer.publicKey });
+
+      const stats = await program.account.referrerStats.fetch(referrerStatsPda(campaign.crowdfund, referrer.publicKey));
+      expect(stats.referredAmount.toNumber()).to.equal(2000);
+      expect((await fetchCampaign(campaign)).referredAmount.toNumber()).to.equal(2000);
+
+      // 结果确定前不能领取
+      await expectError(claimReferralReward(campaign, referrer), "ReferralNotClaimable");
+
+      await waitUntilSettled(campaign);
+      await claimReferralReward(campaign, referrer);
+      expect(await tokenBalance(tokenAccountOf(referrer.publicKey))).to.equal(200);
+
+      await expectError(claimReferralReward(campaign, referrer), "ReferralNotClaimable");
+    });
+
+    it("rejects share changes from other wallets or after opening, and self-referrals", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const stranger = await newWallet();
+      const donor = await newDonor();
+
+      await expectError(setReferralShare(campaign, stranger, 1000), "Unauthorized");
+      await setReferralShare(campaign, campaign.maker, 1000);
+
+      await waitUntil(campaign.startTime);
+      // 开放捐款后比例锁定
+      await expectError(setReferralShare(campaign, campaign.maker, 2000), "ReferralShareLocked");
+      await expectError(donate(campaign, donor, 1000, { referrer: donor.publicKey }), "InvalidReferrer");
+    });
+  });
+
+  describe("vesting", () => {
+    it("releases the raised funds linearly from the success time", async () => {
+      const vestingDuration = 30;
+      const campaign = await createCampaign({ target: 1000, vesting: { cliff: 0, duration: vestingDuration } });
+      const donor = await newDonor();
+      const ownerTokenAccount = tokenAccountOf(campaign.maker.publicKey);
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+      const succeededAt = (await fetchCampaign(campaign)).succeededAt.toNumber();
+
+      // 结束时只归属了一部分
+      await waitUntilSettled(campaign);
+      await withdraw(campaign);
+      const firstWithdrawal = await tokenBalance(ownerTokenAccount);
+      expect(firstWithdrawal).to.be.greaterThan(0).and.lessThan(1000);
+      expect((await fetchCampaign(campaign)).withdrawnAmount.toNumber()).to.equal(firstWithdrawal);
+
+      await waitUntil(succeededAt + vestingDuration);
+      await withdraw(campaign);
+      expect(await tokenBalance(ownerTokenAccount)).to.equal(1000);
+      expect(await tokenBalance(campaign.escrow)).to.equal(0);
+      await expectError(withdraw(campaign), "NothingVested");
+    });
+
+    it("rejects invalid schedules and withdrawals before the cliff", async () => {
+      await expectError(createCampaign({ vesting: { cliff: 20, duration: 10 } }), "InvalidVestingSchedule");
+      await expectError(createCampaign({ vesting: { cliff: 0, duration: 0 } }), "InvalidVestingSchedule");
+
+      const campaign = await createCampaign({ target: 1000, vesting: { cliff: 600, duration: 1200 } });
+      const donor = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+
+      await waitUntilSettled(campaign);
+      await expectError(withdraw(campaign), "NothingVested");
+    });
+  });
+
+  describe("beneficiary changes", () => {
+    async function proposeBeneficiary(campaign: TestCampaign, owner: Keypair, newBeneficiary: PublicKey) {
+      return program.methods
+        .proposeBeneficiary(newBeneficiary)
+        .accountsPartial({
+          owner: owner.publicKey,
+          config: configPda,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
     }
+
+    async function applyBeneficiaryChange(campaign: TestCampaign, owner: Keypair = campaign.maker) {
+      return program.methods
+        .applyBeneficiaryChange()
+        .accountsPartial({
+          owner: owner.publicKey,
+          config: configPda,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    it("pays the new beneficiary once the timelock has passed", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const donor = await newDonor();
+      const newBeneficiary = await newWallet();
+
+      await proposeBeneficiary(campaign, campaign.maker, newBeneficiary.publicKey);
+      let campaignData = await fetchCampaign(campaign);
+      expect(campaignData.pendingBeneficiary.equals(newBeneficiary.publicKey)).to.be.true;
+
+      // 时间锁结束前不能生效
+      await expectError(applyBeneficiaryChange(campaign), "BeneficiaryTimelockActive");
+
+      await waitUntil(campaignData.beneficiaryEffectiveAt.toNumber() - 1);
+      await applyBeneficiaryChange(campaign);
+      campaignData = await fetchCampaign(campaign);
+      expect(campaignData.beneficiary.equals(newBeneficiary.publicKey)).to.be.true;
+      expect(campaignData.pendingBeneficiary).to.be.null;
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+
+      await waitUntilSettled(campaign);
+      await expectError(withdraw(campaign, campaign.maker, campaign.maker.publicKey), "Unauthorized");
+      await withdraw(campaign, campaign.maker, newBeneficiary.publicKey);
+      expect(await tokenBalance(tokenAccountOf(newBeneficiary.publicKey))).to.equal(1000);
+    });
+
+    it("rejects proposals from other wallets, unchanged beneficiaries and applying nothing", async () => {
+      const beneficiary = await newWallet();
+      const campaign = await createCampaign({ beneficiary: beneficiary.publicKey });
+      const stranger = await newWallet();
+
+      expect((await fetchCampaign(campaign)).beneficiary.equals(beneficiary.publicKey)).to.be.true;
+
+      await expectError(proposeBeneficiary(campaign, stranger, stranger.publicKey), "Unauthorized");
+      await expectError(proposeBeneficiary(campaign, campaign.maker, beneficiary.publicKey), "InvalidBeneficiary");
+      await expectError(applyBeneficiaryChange(campaign), "InvalidBeneficiary");
+    });
+  });
+
+  describe("co-owners", () => {
+    async function setCoOwners(campaign: TestCampaign, owner: Keypair, coOwners: PublicKey[], approvalThreshold: number) {
+      return program.methods
+        .setCoOwners(coOwners, approvalThreshold)
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    function proposalPda(campaign: TestCampaign, proposalId: number): PublicKey {
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("proposal"), campaign.crowdfund.toBuffer(), new anchor.BN(proposalId).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    }
+
+    // 提案编号按众筹的 proposal_count 递增，提案人自动计为一票
+    async function proposeAction(campaign: TestCampaign, proposer: Keypair, action: any): Promise<PublicKey> {
+      const proposal = proposalPda(campaign, (await fetchCampaign(campaign)).proposalCount.toNumber());
+      await program.methods
+        .proposeAction(action)
+        .accountsPartial({
+          proposer: proposer.publicKey,
+          maker: campaign.maker.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+          proposal,
+        })
+        .signers([proposer])
+        .rpc();
+      return proposal;
+    }
+
+    async function approveAction(campaign: TestCampaign, approver: Keypair, proposal: PublicKey) {
+      return program.methods
+        .approveAction()
+        .accountsPartial({
+          approver: approver.publicKey,
+          maker: campaign.maker.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+          proposal,
+        })
+        .signers([approver])
+        .rpc();
+    }
+
+    // 提款提案需要传入代币相关账户，其他提案传 null
+    async function executeAction(campaign: TestCampaign, executor: Keypair, proposal: PublicKey, accounts: object = {}) {
+      return program.methods
+        .executeAction()
+        .accountsPartial({
+          executor: executor.publicKey,
+          maker: campaign.maker.publicKey,
+          config: configPda,
+          crowdfundAccount: campaign.crowdfund,
+          proposal,
+          beneficiaryBlocklist: blockedPda(campaign.beneficiary),
+          mint: null,
+          campaignTokenAccount: null,
+          withdrawTokenAccount: null,
+          tokenProgram: null,
+          ...accounts,
+        })
+        .signers([executor])
+        .rpc();
+    }
+
+    it("routes a beneficiary change and the withdrawal through co-owner approval", async () => {
+      const campaign = await createCampaign({ target: 1000, duration: 20 });
+      const coOwner = await newWallet();
+      const donor = await newDonor();
+      const newBeneficiary = await newWallet();
+
+      await setCoOwners(campaign, campaign.maker, [coOwner.publicKey], 2);
+
+      // 设置共同所有者后不能再单独变更收款人
+      await expectError(
+        program.methods
+          .proposeBeneficiary(newBeneficiary.publicKey)
+          .accountsPartial({ owner: campaign.maker.publicKey, config: configPda, crowdfundAccount: campaign.crowdfund })
+          .signers([campaign.maker])
+          .rpc(),
+        "ApprovalRequired"
+      );
+
+      const beneficiaryProposal = await proposeAction(campaign, coOwner, {
+        proposeBeneficiary: { newBeneficiary: newBeneficiary.publicKey },
+      });
+      await approveAction(campaign, campaign.maker, beneficiaryProposal);
+      await executeAction(campaign, coOwner, beneficiaryProposal);
+      expect((await program.account.proposal.fetch(beneficiaryProposal)).isExecuted).to.be.true;
+
+      const effectiveAt = (await fetchCampaign(campaign)).beneficiaryEffectiveAt.toNumber();
+      await waitUntil(effectiveAt - 1);
+      await program.methods
+        .applyBeneficiaryChange()
+        .accountsPartial({ owner: campaign.maker.publicKey, config: configPda, crowdfundAccount: campaign.crowdfund })
+        .signers([campaign.maker])
+        .rpc();
+      campaign.beneficiary = newBeneficiary.publicKey;
+      expect((await fetchCampaign(campaign)).beneficiary.equals(newBeneficiary.publicKey)).to.be.true;
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+
+      await waitUntilSettled(campaign);
+      await expectError(withdraw(campaign), "ApprovalRequired");
+
+      // 执行提款提案时收款人的代币账户需已存在
+      const withdrawTokenAccount = await fundTokens(newBeneficiary.publicKey, 0);
+      const withdrawProposal = await proposeAction(campaign, campaign.maker, { withdraw: {} });
+      await approveAction(campaign, coOwner, withdrawProposal);
+      await executeAction(campaign, coOwner, withdrawProposal, {
+        mint,
+        campaignTokenAccount: campaign.escrow,
+        withdrawTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      });
+
+      expect(await tokenBalance(withdrawTokenAccount)).to.equal(1000);
+      expect(await tokenBalance(campaign.escrow)).to.equal(0);
+    });
+
+    it("rejects invalid co-owner sets, outsiders and executing without enough approvals", async () => {
+      const campaign = await createCampaign();
+      const coOwner = await newWallet();
+      const stranger = await newWallet();
+
+      await expectError(setCoOwners(campaign, stranger, [coOwner.publicKey], 2), "Unauthorized");
+      await expectError(setCoOwners(campaign, campaign.maker, [], 1), "InvalidCoOwners");
+      await expectError(setCoOwners(campaign, campaign.maker, [coOwner.publicKey], 3), "InvalidCoOwners");
+
+      await setCoOwners(campaign, campaign.maker, [coOwner.publicKey], 2);
+      // 只能直接设置一次，之后需通过提案更换
+      await expectError(setCoOwners(campaign, campaign.maker, [stranger.publicKey], 2), "InvalidCoOwners");
+
+      await expectError(proposeAction(campaign, stranger, { cancel: {} }), "NotCoOwner");
+
+      const proposal = await proposeAction(campaign, campaign.maker, { cancel: {} });
+      await expectError(approveAction(campaign, campaign.maker, proposal), "AlreadyApproved");
+      await expectError(approveAction(campaign, stranger, proposal), "NotCoOwner");
+      await expectError(executeAction(campaign, campaign.maker, proposal), "NotEnoughApprovals");
+    });
+  });
+
+  describe("ownership transfer", () => {
+    async function proposeOwner(campaign: TestCampaign, owner: Keypair, newOwner: PublicKey) {
+      return program.methods
+        .proposeOwner(newOwner)
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    async function acceptOwner(campaign: TestCampaign, newOwner: Keypair) {
+      return program.methods
+        .acceptOwner()
+        .accountsPartial({
+          newOwner: newOwner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([newOwner])
+        .rpc();
+    }
+
+    it("hands the campaign to the new owner once they accept", async () => {
+      const campaign = await createCampaign();
+      const newOwner = await newWallet();
+
+      await proposeOwner(campaign, campaign.maker, newOwner.publicKey);
+      // 确认前原所有者仍然有效
+      expect((await fetchCampaign(campaign)).owner.equals(campaign.maker.publicKey)).to.be.true;
+
+      await acceptOwner(campaign, newOwner);
+      const campaignData = await fetchCampaign(campaign);
+      expect(campaignData.owner.equals(newOwner.publicKey)).to.be.true;
+      expect(campaignData.pendingOwner).to.be.null;
+      // PDA 仍由创建者派生
+      expect(campaignData.creator.equals(campaign.maker.publicKey)).to.be.true;
+
+      await pauseCampaign(campaign, newOwner);
+      expect((await fetchCampaign(campaign)).isPaused).to.be.true;
+    });
+
+    it("rejects proposals from other wallets, acceptance by anyone else and the previous owner afterwards", async () => {
+      const campaign = await createCampaign();
+      const newOwner = await newWallet();
+      const stranger = await newWallet();
+
+      await expectError(proposeOwner(campaign, stranger, stranger.publicKey), "Unauthorized");
+      await expectError(proposeOwner(campaign, campaign.maker, campaign.maker.publicKey), "InvalidOwner");
+
+      await proposeOwner(campaign, campaign.maker, newOwner.publicKey);
+      await expectError(acceptOwner(campaign, stranger), "InvalidOwner");
+      await acceptOwner(campaign, newOwner);
+
+      await expectError(pauseCampaign(campaign, campaign.maker), "Unauthorized");
+      await expectError(acceptOwner(campaign, newOwner), "InvalidOwner");
+    });
+  });
+
+  describe("allowlist", () => {
+    // 提前访问窗口在公开开始前 EARLY_ACCESS 秒打开
+    const EARLY_ACCESS = 10;
+
+    async function setAllowlist(
+      campaign: TestCampaign,
+      owner: Keypair,
+      root: Buffer | null,
+      allowlistOnly: boolean,
+      earlyAccessStart: number
+    ) {
+      return program.methods
+        .setAllowlist(root ? Array.from(root) : null, allowlistOnly, new anchor.BN(earlyAccessStart))
+        .accountsPartial({
+          owner: owner.publicKey,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([owner])
+        .rpc();
+    }
+
+    // 白名单叶子为钱包公钥的哈希
+    function allowlistTree(wallets: Keypair[]): MerkleTree {
+      const leaves = wallets.map((wallet) => sha256(wallet.publicKey.toBuffer()));
+      return new MerkleTree(leaves, sha256, { sortPairs: true, duplicateOdd: true });
+    }
+
+    function allowlistProof(tree: MerkleTree, wallet: Keypair): number[][] {
+      return getMerkleProofForLeaf(tree, sha256(wallet.publicKey.toBuffer()));
+    }
+
+    it("lets allowlisted wallets donate during early access and everyone else after the start", async () => {
+      const campaign = await createCampaign({ target: 1_000_000, startTime: (await chainNow()) + EARLY_ACCESS + 5 });
+      const [listed, other, outsider] = [await newDonor(), await newDonor(), await newDonor()];
+      const tree = allowlistTree([listed, other]);
+      const earlyAccessStart = campaign.startTime - EARLY_ACCESS;
+
+      await setAllowlist(campaign, campaign.maker, tree.getRoot(), false, earlyAccessStart);
+      const campaignData = await fetchCampaign(campaign);
+      expect(Buffer.from(campaignData.allowlistRoot).equals(tree.getRoot())).to.be.true;
+      expect(campaignData.earlyAccessStart.toNumber()).to.equal(earlyAccessStart);
+
+      await waitUntil(earlyAccessStart);
+      await donate(campaign, listed, 1000, { allowlistProof: allowlistProof(tree, listed) });
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(1000);
+
+      // 不在白名单的钱包要等到公开开始
+      await expectError(donate(campaign, outsider, 1000), "NoStared");
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, outsider, 1000);
+      expect((await fetchCampaign(campaign)).raisedAmount.toNumber()).to.equal(2000);
+    });
+
+    it("rejects wallets outside an allowlist-only campaign and early pledges recorded for others", async () => {
+      const campaign = await createCampaign({ target: 1_000_000, startTime: (await chainNow()) + EARLY_ACCESS + 5 });
+      const [listed, other, outsider] = [await newDonor(), await newDonor(), await newDonor()];
+      const stranger = await newWallet();
+      const tree = allowlistTree([listed, other]);
+      const earlyAccessStart = campaign.startTime - EARLY_ACCESS;
+
+      await expectError(setAllowlist(campaign, stranger, tree.getRoot(), true, 0), "Unauthorized");
+      await expectError(setAllowlist(campaign, campaign.maker, null, true, 0), "InvalidAllowlist");
+      await expectError(
+        setAllowlist(campaign, campaign.maker, tree.getRoot(), true, campaign.startTime),
+        "InvalidAllowlist"
+      );
+      await setAllowlist(campaign, campaign.maker, tree.getRoot(), true, earlyAccessStart);
+
+      await waitUntil(earlyAccessStart);
+      // 证明与出资钱包不匹配
+      await expectError(
+        donate(campaign, outsider, 1000, { allowlistProof: allowlistProof(tree, listed) }),
+        "NotAllowlisted"
+      );
+      // 白名单钱包不能把提前访问的捐款记到其他钱包名下
+      await expectError(
+        donate(campaign, listed, 1000, { beneficiary: outsider.publicKey, allowlistProof: allowlistProof(tree, listed) }),
+        "NotAllowlisted"
+      );
+
+      await waitUntil(campaign.startTime);
+      // 仅限白名单的众筹开始后仍需提供证明
+      await expectError(donate(campaign, other, 1000), "NotAllowlisted");
+      await donate(campaign, other, 1000, { allowlistProof: allowlistProof(tree, other) });
+    });
+  });
+
+  describe("blocklist", () => {
+    it("stops blocked wallets from donating or receiving a withdrawal until they are unblocked", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const donor = await newDonor();
+
+      await blockWallet(donor.publicKey);
+      expect((await program.account.blockedWallet.fetch(blockedPda(donor.publicKey))).wallet.equals(donor.publicKey)).to.be.true;
+
+      await waitUntil(campaign.startTime);
+      await expectError(donate(campaign, donor, 1000), "Blocked");
+
+      await unblockWallet(donor.publicKey);
+      expect(await connection.getAccountInfo(blockedPda(donor.publicKey))).to.be.null;
+      await donate(campaign, donor, 1000);
+
+      // 收款人被封禁时不能提款
+      await blockWallet(campaign.beneficiary);
+      await waitUntilSettled(campaign);
+      await expectError(withdraw(campaign), "Blocked");
+
+      await unblockWallet(campaign.beneficiary);
+      await withdraw(campaign);
+      expect(await tokenBalance(tokenAccountOf(campaign.beneficiary))).to.equal(1000);
+    });
+
+    it("rejects blocking by other wallets and donations funded by or recorded for blocked wallets", async () => {
+      const campaign = await createCampaign({ target: 1_000_000 });
+      const donor = await newDonor();
+      const blocked = await newDonor();
+      const stranger = await newWallet();
+
+      await expectError(blockWallet(blocked.publicKey, stranger), "Unauthorized");
+      await blockWallet(blocked.publicKey);
+
+      try {
+        await waitUntil(campaign.startTime);
+        await expectError(donate(campaign, donor, 1000, { beneficiary: blocked.publicKey }), "Blocked");
+        await expectError(donate(campaign, blocked, 1000, { beneficiary: donor.publicKey }), "Blocked");
+        await expectError(blockWallet(blocked.publicKey), "already in use");
+      } finally {
+        await unblockWallet(blocked.publicKey);
+      }
+    });
+  });
+
+  describe("disputes", () => {
+    // 延长争议期，测试中有足够时间标记
+    const LONG_DISPUTE_WINDOW = 30;
+
+    before(async () => {
+      await setDisputePolicy(LONG_DISPUTE_WINDOW);
+    });
+
+    after(async () => {
+      await setDisputePolicy(DISPUTE_WINDOW);
+    });
+
+    async function flagCampaign(campaign: TestCampaign, donor: Keypair) {
+      return program.methods
+        .flagCampaign()
+        .accountsPartial({
+          donor: donor.publicKey,
+          maker: campaign.maker.publicKey,
+          config: configPda,
+          crowdfundAccount: campaign.crowdfund,
+          donationRecordAccount: donationPda(campaign.crowdfund, donor.publicKey),
+        })
+        .signers([donor])
+        .rpc();
+    }
+
+    async function resolveDispute(campaign: TestCampaign, arbiter: Keypair, release: boolean) {
+      return program.methods
+        .resolveDispute(release)
+        .accountsPartial({
+          arbiter: arbiter.publicKey,
+          maker: campaign.maker.publicKey,
+          config: configPda,
+          crowdfundAccount: campaign.crowdfund,
+        })
+        .signers([arbiter])
+        .rpc();
+    }
+
+    it("lets backers dispute a success and the arbiter fail it so they can get refunds", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const donor = await newDonor();
+      const stranger = await newWallet();
+      const balance = await tokenBalance(tokenAccountOf(donor.publicKey));
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, donor, 1000);
+      expect((await fetchCampaign(campaign)).state).to.equal(1);
+
+      // 标记金额超过阈值，进入争议状态
+      await flagCampaign(campaign, donor);
+      let campaignData = await fetchCampaign(campaign);
+      expect(campaignData.flaggedAmount.toNumber()).to.equal(1000);
+      expect(campaignData.state).to.equal(3);
+
+      await waitUntil(campaign.endTime);
+      await expectError(withdraw(campaign), "WithdrawalNotAllowed");
+      await expectError(finalize(campaign), "CampaignDisputed");
+      await expectError(resolveDispute(campaign, stranger, false), "Unauthorized");
+
+      await resolveDispute(campaign, payer, false);
+      campaignData = await fetchCampaign(campaign);
+      expect(campaignData.state).to.equal(2);
+      expect(campaignData.isCancelled).to.be.true;
+      expect(campaignData.isDisputeResolved).to.be.true;
+
+      await refund(campaign, donor.publicKey);
+      expect(await tokenBalance(tokenAccountOf(donor.publicKey))).to.equal(balance);
+    });
+
+    it("rejects flags before success or twice, withdrawals during the window and resolving undisputed campaigns", async () => {
+      const campaign = await createCampaign({ target: 1000 });
+      const large = await newDonor();
+      const small = await newDonor();
+
+      await waitUntil(campaign.startTime);
+      await donate(campaign, small, 50);
+      await expectError(flagCampaign(campaign, small), "DisputeNotAllowed");
+
+      await donate(campaign, large, 950);
+      // 标记金额未超过阈值，众筹仍为成功
+      await flagCampaign(campaign, small);
+      expect((await fetchCampaign(campaign)).state).to.equal(1);
+      await expectError(flagCampaign(campaign, small), "AlreadyFlagged");
+      await expectError(resolveDispute(campaign, payer, true), "DisputeNotAllowed");
+
+      await waitUntil(campaign.endTime);
+      await expectError(withdraw(campaign), "DisputeWindowActive");
+    });
   });
-});
\ No newline at end of file
+});